@@ -22,12 +22,26 @@ def main():
             except:
                 print(path)
                 raise
+            block = {
+                "block_id": block_id,
+                "texturing": texturing,
+                "part": suffix,
+                "avg_color": color,
+            }
+            lit = block_id != "redstone_lamp" or suffix == "on"
+            if block_id in EMISSIVE_BLOCKS and lit:
+                block["emissive"] = True
+            blocks.append(block)
+
+    for block_id in TRANSLUCENT_BLOCKS:
+        for suffix, path in get_textures(block_id):
             blocks.append(
                 {
                     "block_id": block_id,
-                    "texturing": texturing,
+                    "texturing": Texturing.UNIFORM,
                     "part": suffix,
-                    "avg_color": color,
+                    "avg_color": average_color(path, min_alpha=1),
+                    "translucent": True,
                 }
             )
 
@@ -63,13 +77,13 @@ def get_textures(block_id: str) -> Iterator[Tuple[Optional[str], Path]]:
         yield (cleaned, texture_dir / path)
 
 
-def average_color(path: Path) -> Tuple[int, int, int]:
+def average_color(path: Path, min_alpha: int = 128) -> Tuple[int, int, int]:
     im = Image.open(path).convert("RGBA")
     rs, gs, bs = 0, 0, 0
     count = 0
     asdf = list(im.getdata())
     for r, g, b, a in asdf:
-        if a < 128:
+        if a < min_alpha:
             continue
         rs += r
         gs += g
@@ -324,6 +338,7 @@ SUPPORTED_BLOCKS = [
     ("red_terracotta", Texturing.UNIFORM),
     ("red_wool", Texturing.UNIFORM),
     ("redstone_block", Texturing.UNIFORM),
+    ("redstone_lamp", Texturing.UNIFORM),
     ("redstone_ore", Texturing.UNIFORM),
     ("respawn_anchor", Texturing.UPRIGHT),
     ("rooted_dirt", Texturing.UNIFORM),
@@ -396,6 +411,43 @@ SUPPORTED_BLOCKS = [
     ("yellow_wool", Texturing.UNIFORM),
 ]
 
+# Blocks that can be seen through, for texels that can be too. Their colors
+# average every pixel that isn't fully transparent.
+TRANSLUCENT_BLOCKS = [
+    "black_stained_glass",
+    "blue_stained_glass",
+    "brown_stained_glass",
+    "cyan_stained_glass",
+    "glass",
+    "gray_stained_glass",
+    "green_stained_glass",
+    "honey_block",
+    "ice",
+    "light_blue_stained_glass",
+    "light_gray_stained_glass",
+    "lime_stained_glass",
+    "magenta_stained_glass",
+    "orange_stained_glass",
+    "pink_stained_glass",
+    "purple_stained_glass",
+    "red_stained_glass",
+    "slime_block",
+    "white_stained_glass",
+    "yellow_stained_glass",
+]
+
+# Blocks that give off light, for texels that glow. Only the lit texture of a
+# redstone lamp counts.
+EMISSIVE_BLOCKS = {
+    "glowstone",
+    "ochre_froglight",
+    "pearlescent_froglight",
+    "redstone_lamp",
+    "sea_lantern",
+    "shroomlight",
+    "verdant_froglight",
+}
+
 
 class JSONEncoder(json.JSONEncoder):
     def default(self, o):
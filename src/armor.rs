@@ -0,0 +1,182 @@
+use crate::atlas::Atlas;
+use crate::drawing::{Cuboid, Dimensions3D, FaceOrientations, Primitive, Priority, TextureOffsets};
+use crate::model::{JsonModel, Model};
+use crate::transform::Transform;
+use crate::{open_texture, tint, Error, TEXTURE_DIR};
+use image::{imageops, RgbaImage};
+use serde::Deserialize;
+use std::path::Path;
+
+/// The color of undyed leather armor.
+const LEATHER: [u8; 3] = [0xa0, 0x65, 0x40];
+
+#[derive(Copy, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Slot {
+    Helmet,
+    Chestplate,
+    Leggings,
+    Boots,
+}
+
+impl Slot {
+    pub const ALL: [Slot; 4] = [Slot::Helmet, Slot::Chestplate, Slot::Leggings, Slot::Boots];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "helmet" => Some(Slot::Helmet),
+            "chestplate" => Some(Slot::Chestplate),
+            "leggings" => Some(Slot::Leggings),
+            "boots" => Some(Slot::Boots),
+            _ => None,
+        }
+    }
+}
+
+/// Where a piece of armor is drawn on a model, taken from `models.json`.
+#[derive(Deserialize)]
+pub struct ArmorLayer {
+    slot: Slot,
+    /// Cuboid part the armor is drawn around.
+    pub part: String,
+    /// Which of the material's two textures to use.
+    layer: u32,
+    /// Top left corner of the part's box in the armor texture.
+    uv: [u32; 2],
+    /// How many texels the armor stands off the part on every side.
+    inflate: u32,
+    /// The size of the armor's box before it's inflated, centred on the part,
+    /// for parts thinner than the limbs armor is made for.
+    size: Option<[u32; 3]>,
+}
+
+/// A piece of armor being worn, such as `diamond` or `leather:3c44aa`.
+pub struct Armor {
+    pub slot: Slot,
+    pub material: String,
+    /// Dye color, only used for leather.
+    pub color: Option<[u8; 3]>,
+}
+
+impl Armor {
+    pub fn parse(slot: Slot, value: &str) -> Option<Self> {
+        let (material, color) = match value.split_once(':') {
+            Some((material, hex)) => (material, Some(parse_color(hex)?)),
+            None => (value, None),
+        };
+
+        Some(Self {
+            slot,
+            material: material.to_string(),
+            color,
+        })
+    }
+
+    /// The armor's texture for `layer`, dyed and with its overlay if it's
+    /// leather.
+    fn texture(&self, layer: u32) -> Result<RgbaImage, Error> {
+        let path = format!(
+            "{}/models/armor/{}_layer_{}.png",
+            TEXTURE_DIR, self.material, layer
+        );
+        let mut texture = open_texture(&path)?;
+        if self.material != "leather" {
+            return Ok(texture);
+        }
+
+        tint(&mut texture, self.color.unwrap_or(LEATHER));
+
+        let overlay = format!(
+            "{}/models/armor/leather_layer_{}_overlay.png",
+            TEXTURE_DIR, layer
+        );
+        if Path::new(&overlay).exists() {
+            imageops::overlay(&mut texture, &open_texture(&overlay)?, 0, 0);
+        }
+
+        Ok(texture)
+    }
+}
+
+fn parse_color(hex: &str) -> Option<[u8; 3]> {
+    let hex = hex.strip_prefix('#').unwrap_or(hex);
+    if hex.len() != 6 {
+        return None;
+    }
+
+    let color = u32::from_str_radix(hex, 16).ok()?;
+    Some([(color >> 16) as u8, (color >> 8) as u8, color as u8])
+}
+
+/// Armor pieces drawn around the parts of a posed model.
+pub struct ArmorModel {
+    cuboids: Vec<Cuboid>,
+}
+
+impl ArmorModel {
+    /// Builds the armor `model` is wearing, adding the textures, stretched to
+    /// fit around each part, to `atlas`.
+    pub fn new(model: &JsonModel, armor: &[Armor], atlas: &mut Atlas) -> Result<Self, Error> {
+        let mut cuboids = vec![];
+        if !armor.is_empty() && model.armor.is_empty() {
+            println!("Skipping armor, which {} can't wear", model.name);
+        }
+        // Layers are drawn in the model's order, so later ones win where
+        // pieces overlap.
+        for layer in model.armor.iter() {
+            let Some(piece) = armor.iter().find(|a| a.slot == layer.slot) else {
+                continue;
+            };
+            let Some((dimensions, mirror, position)) = model.cuboid(&layer.part) else {
+                println!("Skipping armor for unknown part \"{}\"", layer.part);
+                continue;
+            };
+
+            let texture = piece.texture(layer.layer)?;
+            let mut cuboid = inflate(&texture, layer, dimensions, position, atlas);
+            // Armor on a mirrored part, such as a left leg, is mirrored too.
+            cuboid.mirror = mirror;
+            cuboids.push(cuboid);
+        }
+
+        Ok(Self { cuboids })
+    }
+}
+
+impl Model for ArmorModel {
+    fn parts(&self) -> Vec<Primitive> {
+        self.cuboids.iter().cloned().map(Primitive::Cuboid).collect()
+    }
+}
+
+/// A cuboid `layer.inflate` larger than the part on every side, with each face
+/// of the part's texture stretched to cover it.
+fn inflate(
+    texture: &RgbaImage,
+    layer: &ArmorLayer,
+    dimensions: Dimensions3D,
+    position: Transform,
+    atlas: &mut Atlas,
+) -> Cuboid {
+    let part = [dimensions.x, dimensions.y, dimensions.z];
+    let size = layer.size.unwrap_or(part);
+    let source = TextureOffsets::from_box((layer.uv[0], layer.uv[1]), size.into());
+    let orientations = FaceOrientations::default();
+    // Armor textures are the game's own, with a texel per unit.
+    let offsets = atlas.add_stretched(texture, 1, source, orientations, size.into(), layer.inflate);
+
+    let [gx, gy, gz] =
+        [0, 1, 2].map(|i| layer.inflate as i32 + (size[i] as i32 - part[i] as i32) / 2);
+    Cuboid {
+        dimensions: size.map(|d| d + 2 * layer.inflate).into(),
+        offsets,
+        orientations,
+        mirror: false,
+        fill: None,
+        priority: Priority {
+            order: 0,
+            overlay: true,
+        },
+        position: Transform::new().translate(-gx, gy, gz).then(&position),
+    }
+}
@@ -0,0 +1,104 @@
+use crate::drawing::{Dimensions3D, FaceOrientations, TextureOffsets, UvOrientation};
+use image::{imageops, Rgba, RgbaImage};
+
+/// Combines several textures into a single image so that models drawing from
+/// more than one texture can still be rendered with one `Draw` call.
+///
+/// Every texture in an atlas has `scale` texels per unit, and offsets are
+/// given in units.
+pub struct Atlas {
+    image: RgbaImage,
+    scale: u32,
+}
+
+impl Atlas {
+    pub fn new(scale: u32) -> Self {
+        Self {
+            image: RgbaImage::new(0, 0),
+            scale,
+        }
+    }
+
+    /// Starts an atlas from an existing texture, which keeps its offsets.
+    pub fn with_base(image: RgbaImage, scale: u32) -> Self {
+        Self { image, scale }
+    }
+
+    /// Appends `image` below the current contents and returns its offset.
+    pub fn add(&mut self, image: &RgbaImage) -> (u32, u32) {
+        // Rounding up keeps every offset on a whole unit.
+        let y = self.image.height().next_multiple_of(self.scale);
+        let width = self.image.width().max(image.width());
+        let mut grown = RgbaImage::new(width, y + image.height());
+        imageops::replace(&mut grown, &self.image, 0, 0);
+        imageops::replace(&mut grown, image, 0, y as i64);
+        self.image = grown;
+        (0, y / self.scale)
+    }
+
+    /// Reserves a fully transparent region of `width` by `height` units,
+    /// which draws nothing.
+    pub fn blank(&mut self, width: u32, height: u32) -> (u32, u32) {
+        self.add(&RgbaImage::new(width * self.scale, height * self.scale))
+    }
+
+    pub fn scale(&self) -> u32 {
+        self.scale
+    }
+
+    /// Copies the faces of a box at `offsets` in `source`, which has
+    /// `source_scale` texels per unit, stretching each to fit a box `grow`
+    /// larger on every side, and returns their offsets. Faces keep their
+    /// `orientations`, so turned faces are copied on their side.
+    pub fn add_stretched(
+        &mut self,
+        source: &RgbaImage,
+        source_scale: u32,
+        offsets: TextureOffsets,
+        orientations: FaceOrientations,
+        dimensions: Dimensions3D,
+        grow: u32,
+    ) -> TextureOffsets {
+        let Dimensions3D { x, y, z } = dimensions;
+        let scale = self.scale;
+        let mut stretch = |(u, v): (u32, u32), orientation: UvOrientation, size: (u32, u32)| {
+            let (width, height) = orientation.source_size(size);
+            let stretched = (width + 2 * grow, height + 2 * grow);
+            let face = RgbaImage::from_fn(stretched.0 * scale, stretched.1 * scale, |i, j| {
+                let i = stretched_texel(i, width, stretched.0, scale, source_scale);
+                let j = stretched_texel(j, height, stretched.1, scale, source_scale);
+                let texel = source.get_pixel_checked(u * source_scale + i, v * source_scale + j);
+                texel.copied().unwrap_or(Rgba([0, 0, 0, 0]))
+            });
+            self.add(&face)
+        };
+
+        TextureOffsets {
+            top: stretch(offsets.top, orientations.top, (x, z)),
+            bottom: stretch(offsets.bottom, orientations.bottom, (x, z)),
+            right: stretch(offsets.right, orientations.right, (z, y)),
+            left: stretch(offsets.left, orientations.left, (z, y)),
+            front: stretch(offsets.front, orientations.front, (x, y)),
+            back: stretch(offsets.back, orientations.back, (x, y)),
+        }
+    }
+
+    pub fn image(&self) -> &RgbaImage {
+        &self.image
+    }
+
+    pub fn into_image(self) -> RgbaImage {
+        self.image
+    }
+}
+
+/// Which texel of a face `from` units long, with `source_scale` texels per
+/// unit, lands `t` texels along it once stretched to `to` units of `scale`
+/// texels. Whole units are repeated, picked like resizing at a texel per unit
+/// would, so faces stretch the same at any scale and keep the detail within
+/// each unit.
+fn stretched_texel(t: u32, from: u32, to: u32, scale: u32, source_scale: u32) -> u32 {
+    let unit = ((t / scale) as f32 + 0.5) * from as f32 / to as f32;
+    let unit = (unit as u32).min(from.saturating_sub(1));
+    unit * source_scale + t % scale * source_scale / scale
+}
@@ -0,0 +1,332 @@
+use crate::atlas::Atlas;
+use crate::drawing::{Cuboid, FaceOrientations, Primitive, Priority, TextureOffsets};
+use crate::model::Model;
+use crate::transform::{Axis, Plane, Transform};
+use crate::Error;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs::File;
+
+/// An entity model from a Bedrock resource pack's `.geo.json` files.
+///
+/// Bedrock models face north with X mirrored, so they are turned to face
+/// south like the models in `models.json`, which keeps the X axis as is and
+/// flips Z.
+pub struct BedrockModel {
+    name: String,
+    bones: Vec<Bone>,
+}
+
+impl BedrockModel {
+    /// Loads `identifier` from `path`, or the first geometry if none is given.
+    pub fn load(path: &str, identifier: Option<&str>) -> Result<Self, Error> {
+        let file = File::open(path)?;
+        let file: GeometryFile = serde_json::from_reader(file)?;
+
+        let mut geometries: HashMap<String, Vec<Bone>> = HashMap::new();
+        let mut names = vec![];
+        for geometry in file.geometry.into_iter() {
+            names.push(geometry.description.identifier.clone());
+            geometries.insert(geometry.description.identifier, geometry.bones);
+        }
+
+        // The older format keys geometry by name, optionally inheriting the
+        // bones of another geometry with `geometry.child:geometry.parent`.
+        let mut legacy = file
+            .legacy
+            .into_iter()
+            .filter(|(key, _)| key.starts_with("geometry."))
+            .collect::<Vec<_>>();
+        legacy.sort_by_key(|(key, _)| key.contains(':'));
+        for (key, value) in legacy.into_iter() {
+            let geometry: LegacyGeometry = serde_json::from_value(value)?;
+            let (name, parent) = match key.split_once(':') {
+                Some((name, parent)) => (name.to_string(), Some(parent)),
+                None => (key, None),
+            };
+
+            let mut bones = match parent {
+                Some(parent) => geometries
+                    .get(parent)
+                    .ok_or_else(|| Error::UnsupportedModel(parent.to_string()))?
+                    .clone(),
+                None => vec![],
+            };
+            for bone in geometry.bones.into_iter() {
+                match bones.iter_mut().find(|b| b.name == bone.name) {
+                    Some(existing) => *existing = bone,
+                    None => bones.push(bone),
+                }
+            }
+
+            names.push(name.clone());
+            geometries.insert(name, bones);
+        }
+
+        let name = match identifier {
+            Some(identifier) => identifier.to_string(),
+            None => names
+                .first()
+                .cloned()
+                .ok_or_else(|| Error::UnsupportedModel(path.to_string()))?,
+        };
+
+        let Some(bones) = geometries.remove(&name) else {
+            return Err(Error::UnsupportedModel(name));
+        };
+        Ok(Self { name, bones })
+    }
+
+    /// Checks that every cube's faces are inside a texture `size` units
+    /// across, printing those that aren't.
+    pub fn check_uvs(&self, (width, height): (u32, u32)) -> Result<(), Error> {
+        let mut valid = true;
+        for bone in self.bones.iter() {
+            for (i, cube) in bone.cubes.iter().enumerate() {
+                let Some(offsets) = cube.offsets() else {
+                    continue;
+                };
+
+                let [x, y, z] = cube.size();
+                let faces = [
+                    ("top face", offsets.top, (x, z)),
+                    ("bottom face", offsets.bottom, (x, z)),
+                    ("right face", offsets.right, (z, y)),
+                    ("left face", offsets.left, (z, y)),
+                    ("front face", offsets.front, (x, y)),
+                    ("back face", offsets.back, (x, y)),
+                ];
+                for (face, (u, v), (w, h)) in faces {
+                    if u + w > width || v + h > height {
+                        println!(
+                            "error: {}, cube {} of bone \"{}\": {face} ({u}, {v}) to ({}, {}) \
+                             is outside the {width}x{height} texture",
+                            self.name,
+                            i + 1,
+                            bone.name,
+                            u + w,
+                            v + h
+                        );
+                        valid = false;
+                    }
+                }
+            }
+        }
+
+        match valid {
+            true => Ok(()),
+            false => Err(Error::InvalidModel(self.name.clone())),
+        }
+    }
+
+    /// Points the faces of flat cubes that have no area, like the edges of
+    /// wings, at a blank region of `atlas`, so that drawing the cubes a block
+    /// thick doesn't show the texels next to them.
+    pub fn blank_flat_faces(&mut self, atlas: &mut Atlas) {
+        let cubes = || self.bones.iter().flat_map(|b| b.cubes.iter());
+        let flat = cubes().filter(|c| c.size().contains(&0));
+        let Some(side) = flat.filter_map(|c| c.dimensions().into_iter().max()).max() else {
+            return;
+        };
+
+        let blank = atlas.blank(side, side);
+        for cube in self.bones.iter_mut().flat_map(|b| b.cubes.iter_mut()) {
+            cube.blank = Some(blank);
+        }
+    }
+
+    /// The rotations of `bone` and all of its ancestors, innermost first.
+    fn pose(&self, bone: &Bone) -> Transform {
+        let mut pose = Transform::new();
+        let mut next = Some(bone);
+        // Guards against bones that are their own ancestors.
+        for _ in 0..self.bones.len() {
+            let Some(bone) = next else {
+                break;
+            };
+
+            pose = rotate(pose, bone.rotation, bone.pivot);
+            next = bone
+                .parent
+                .as_ref()
+                .and_then(|p| self.bones.iter().find(|b| &b.name == p));
+        }
+
+        pose
+    }
+}
+
+impl Model for BedrockModel {
+    fn parts(&self) -> Vec<Primitive> {
+        let mut drawables = vec![];
+        for bone in self.bones.iter() {
+            let pose = self.pose(bone);
+            for cube in bone.cubes.iter() {
+                let Some(offsets) = cube.offsets() else {
+                    println!("Skipping cube in {} without UVs for every face.", bone.name);
+                    continue;
+                };
+
+                if cube.inflate != 0.0 {
+                    println!(
+                        "Ignoring inflate on a cube in {}, which isn't supported.",
+                        bone.name
+                    );
+                }
+
+                let [w, h, d] = cube.dimensions();
+                let [x, y, z] = cube.origin.map(|c| c.round() as i32);
+
+                let mut position = Transform::new();
+                if cube.mirror.unwrap_or(bone.mirror) {
+                    position = position.mirror(Plane::YZ).translate(w as i32 + 1, 0, 0);
+                }
+                position = position.translate(x - 1, y + h as i32 - 1, -z - 1);
+                if let Some(rotation) = cube.rotation {
+                    let center = [0, 1, 2].map(|i| cube.origin[i] + cube.size[i] / 2.0);
+                    let pivot = cube.pivot.unwrap_or(center);
+                    position = rotate(position, rotation, pivot);
+                }
+
+                drawables.push(Primitive::Cuboid(Cuboid {
+                    dimensions: [w, h, d].into(),
+                    offsets,
+                    orientations: FaceOrientations::default(),
+                    mirror: false,
+                    fill: None,
+                    priority: Priority::default(),
+                    position: position.then(&pose),
+                }));
+            }
+        }
+
+        drawables
+    }
+}
+
+/// Applies Bedrock's X, Y then Z rotations about `pivot`.
+fn rotate(transform: Transform, angles: [f32; 3], pivot: [f32; 3]) -> Transform {
+    // Bedrock's frame is mirrored, so angles about Y and Z turn the other way.
+    let [x, y, z] = angles;
+    let pivot = [pivot[0], pivot[1], -pivot[2]];
+    transform
+        .rotate_by(Axis::X, x, pivot)
+        .rotate_by(Axis::Y, -y, pivot)
+        .rotate_by(Axis::Z, -z, pivot)
+}
+
+#[derive(Deserialize)]
+struct GeometryFile {
+    #[serde(rename = "minecraft:geometry", default)]
+    geometry: Vec<Geometry>,
+    #[serde(flatten)]
+    legacy: HashMap<String, serde_json::Value>,
+}
+
+#[derive(Deserialize)]
+struct Geometry {
+    description: Description,
+    #[serde(default)]
+    bones: Vec<Bone>,
+}
+
+#[derive(Deserialize)]
+struct Description {
+    identifier: String,
+}
+
+#[derive(Deserialize)]
+struct LegacyGeometry {
+    #[serde(default)]
+    bones: Vec<Bone>,
+}
+
+#[derive(Clone, Deserialize)]
+struct Bone {
+    name: String,
+    parent: Option<String>,
+    #[serde(default)]
+    pivot: [f32; 3],
+    #[serde(default)]
+    rotation: [f32; 3],
+    #[serde(default)]
+    mirror: bool,
+    #[serde(default)]
+    cubes: Vec<Cube>,
+}
+
+#[derive(Clone, Deserialize)]
+struct Cube {
+    origin: [f32; 3],
+    size: [f32; 3],
+    uv: Uv,
+    mirror: Option<bool>,
+    pivot: Option<[f32; 3]>,
+    rotation: Option<[f32; 3]>,
+    #[serde(default)]
+    inflate: f32,
+    /// Where faces with no area read their texels instead, once the model
+    /// has one.
+    #[serde(skip)]
+    blank: Option<(u32, u32)>,
+}
+
+impl Cube {
+    /// The size in whole units, which is zero across cubes that are flat.
+    fn size(&self) -> [u32; 3] {
+        self.size.map(|c| c.round().max(0.0) as u32)
+    }
+
+    /// The size drawn, at least one thick even for cubes that are flat, like
+    /// wings.
+    fn dimensions(&self) -> [u32; 3] {
+        self.size().map(|c| c.max(1))
+    }
+
+    fn offsets(&self) -> Option<TextureOffsets> {
+        let mut offsets = match &self.uv {
+            Uv::Box([u, v]) => TextureOffsets::from_box((*u as u32, *v as u32), self.size().into()),
+            Uv::Faces(faces) => {
+                let face = |name: &str| faces.get(name).map(|f| (f.uv[0] as u32, f.uv[1] as u32));
+                TextureOffsets {
+                    top: face("up")?,
+                    bottom: face("down")?,
+                    right: face("west")?,
+                    left: face("east")?,
+                    front: face("north")?,
+                    back: face("south")?,
+                }
+            }
+        };
+
+        if let Some(blank) = self.blank {
+            let [x, y, z] = self.size();
+            let faces = [
+                (&mut offsets.top, x * z),
+                (&mut offsets.bottom, x * z),
+                (&mut offsets.right, z * y),
+                (&mut offsets.left, z * y),
+                (&mut offsets.front, x * y),
+                (&mut offsets.back, x * y),
+            ];
+            for (offset, area) in faces {
+                if area == 0 {
+                    *offset = blank;
+                }
+            }
+        }
+        Some(offsets)
+    }
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(untagged)]
+enum Uv {
+    Box([f32; 2]),
+    Faces(HashMap<String, FaceUv>),
+}
+
+#[derive(Clone, Deserialize)]
+struct FaceUv {
+    uv: [f32; 2],
+}
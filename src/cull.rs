@@ -0,0 +1,46 @@
+use crate::materials::is_see_through;
+use crate::nbt::{Palette, Structure};
+
+/// What to do with blocks that can't be seen, because every side of them is
+/// against a block that can't be seen through.
+pub enum Cull {
+    Remove,
+    /// Swaps them for a block by its ID, like a cheaper one to gather.
+    Replace(String),
+    /// Only says how many there are.
+    Report,
+}
+
+impl Cull {
+    /// Parses `remove`, `report` or `replace:<block>`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.split_once(':') {
+            Some(("replace", block)) if !block.is_empty() => Some(Cull::Replace(block.to_string())),
+            Some(_) => None,
+            None => match name {
+                "remove" => Some(Cull::Remove),
+                "report" => Some(Cull::Report),
+                _ => None,
+            },
+        }
+    }
+}
+
+/// Culls the hidden blocks of a finished structure and prints what it did.
+pub fn cull(structure: &mut Structure, cull: &Cull) {
+    let hidden = structure.hidden(|p| is_see_through(&p.name));
+    let (count, total) = (hidden.len(), structure.blocks.len());
+    match cull {
+        Cull::Remove => {
+            structure.remove(&hidden);
+            println!("Removed {count} of {total} blocks, which were hidden");
+        }
+        Cull::Replace(id) => {
+            for &pos in hidden.iter() {
+                structure.set(pos, Palette::new(id));
+            }
+            println!("Replaced {count} of {total} blocks, which were hidden, with {id}");
+        }
+        Cull::Report => println!("{count} of {total} blocks are hidden"),
+    }
+}
@@ -1,6 +1,27 @@
+use crate::drawing::Fill;
 use crate::nbt::Structure;
-use image::RgbaImage;
+use crate::texture::Texture;
+use crate::transform::Scale;
+
+/// How everything in a statue is drawn, besides its texture.
+pub struct Settings {
+    /// Blocks per model unit along each axis.
+    pub scale: Scale,
+    /// What fills cuboids that don't choose for themselves.
+    pub fill: Option<Fill>,
+}
 
 pub trait Draw {
-    fn draw(&self, structure: &mut Structure, scaling: u32, image: &RgbaImage);
+    fn draw(&self, structure: &mut Structure, settings: &Settings, texture: &Texture);
+}
+
+/// Several drawables sharing one texture, such as a model and what it holds.
+pub struct Group(pub Vec<Box<dyn Draw>>);
+
+impl Draw for Group {
+    fn draw(&self, structure: &mut Structure, settings: &Settings, texture: &Texture) {
+        for drawable in self.0.iter() {
+            drawable.draw(structure, settings, texture);
+        }
+    }
 }
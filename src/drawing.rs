@@ -1,7 +1,9 @@
-use crate::materials::{find_closest, Direction};
-use crate::nbt::Structure;
+use crate::materials::Direction;
+use crate::nbt::{Palette, Structure};
+use crate::texture::{Region, Texture};
 use crate::transform::{Rotation, Transform};
-use image::{GenericImageView, RgbaImage};
+use image::Rgba;
+use std::collections::HashMap;
 
 #[derive(Clone)]
 pub enum Primitive {
@@ -13,19 +15,306 @@ pub enum Primitive {
 pub struct Cuboid {
     pub dimensions: Dimensions3D,
     pub offsets: TextureOffsets,
+    pub orientations: FaceOrientations,
+    /// Mirrors the texture left to right, so the box looks like a reflection
+    /// of itself, like the game's mirrored boxes.
+    pub mirror: bool,
+    /// What goes inside the box, or `None` for whatever the statue uses.
+    pub fill: Option<Fill>,
+    pub priority: Priority,
     pub position: Transform,
 }
 
+/// Decides which primitive keeps a block that several draw to. Higher orders
+/// win, then layers over what they cover, then surfaces on the outside over
+/// those buried in another part. Ties go to whichever draws last, which for
+/// the faces of one cuboid is the order of `Cuboid::faces`.
+#[derive(Copy, Clone, Default)]
+pub struct Priority {
+    pub order: i32,
+    /// Whether the primitive is a layer over others, like a hat or armor.
+    pub overlay: bool,
+}
+
+impl Priority {
+    fn rank(&self, block: [i32; 3], others: &[&Volume]) -> i64 {
+        let outside = !others.iter().any(|v| v.buries(block));
+        (self.order as i64) << 2 | (self.overlay as i64) << 1 | outside as i64
+    }
+}
+
+/// The blocks a cuboid takes up, for finding surfaces buried in it.
+pub struct Volume {
+    transform: Transform,
+    bounds: ([f64; 3], [f64; 3]),
+    blocks: ([i32; 3], [i32; 3]),
+}
+
+impl Volume {
+    /// Whether `block` is inside the cuboid and not on its surface.
+    fn buries(&self, block: [i32; 3]) -> bool {
+        let (low, high) = self.blocks;
+        if (0..3).any(|i| block[i] <= low[i] || block[i] >= high[i]) {
+            return false;
+        }
+
+        let (min, max) = self.bounds;
+        let [x, y, z] = block;
+        [
+            [x, y, z],
+            [x - 1, y, z],
+            [x + 1, y, z],
+            [x, y - 1, z],
+            [x, y + 1, z],
+            [x, y, z - 1],
+            [x, y, z + 1],
+        ]
+        .into_iter()
+        .all(|b| {
+            let p = self.transform.invert_f64(self.transform.scale.centre(b));
+            (0..3).all(|i| p[i] >= min[i] && p[i] < max[i])
+        })
+    }
+}
+
+/// What fills the inside of a cuboid, which is otherwise left hollow.
+#[derive(Clone)]
+pub enum Fill {
+    Hollow,
+    /// The block the nearest face has there.
+    Nearest,
+    /// A block by its ID, like `stone`.
+    Block(String),
+}
+
+impl Fill {
+    /// Parses `hollow`, `nearest` or `block:<block>`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.split_once(':') {
+            Some(("block", block)) if !block.is_empty() => Some(Fill::Block(block.to_string())),
+            Some(_) => None,
+            None => match name {
+                "hollow" => Some(Fill::Hollow),
+                "nearest" => Some(Fill::Nearest),
+                _ => None,
+            },
+        }
+    }
+}
+
 impl Cuboid {
+    /// Draws the surface of the box, where `others` are the boxes drawn with
+    /// it that it can be buried in.
+    pub fn draw(
+        &self,
+        structure: &mut Structure,
+        texture: &Texture,
+        pre_transform: &Transform,
+        others: &[&Volume],
+    ) {
+        let rank = |block| self.priority.rank(block, others);
+        if self.position.is_axis_aligned() {
+            for (face, brush) in self.faces().into_iter() {
+                face.draw(
+                    structure,
+                    texture,
+                    pre_transform,
+                    &self.position,
+                    brush,
+                    rank,
+                );
+            }
+            return;
+        }
+
+        let transform = pre_transform.clone().then(&self.position);
+        let layer = 1.0 / transform.scale.min();
+        sample(
+            structure,
+            &transform,
+            self.bounds(),
+            Layers::Surface(&rank),
+            |point| self.surface(texture, &transform, point, Some(layer)),
+        );
+    }
+
+    pub fn volume(&self, pre_transform: &Transform) -> Volume {
+        let transform = pre_transform.clone().then(&self.position);
+        let bounds = self.bounds();
+        let blocks = block_bounds(&transform, bounds);
+        Volume {
+            transform,
+            bounds,
+            blocks,
+        }
+    }
+
+    /// Fills the blocks under the surface `draw` covers, leaving any that
+    /// something else has already drawn.
+    pub fn fill(
+        &self,
+        structure: &mut Structure,
+        texture: &Texture,
+        pre_transform: &Transform,
+        fill: &Fill,
+    ) {
+        let transform = pre_transform.clone().then(&self.position);
+        match fill {
+            Fill::Hollow => {}
+            Fill::Nearest => sample(
+                structure,
+                &transform,
+                self.bounds(),
+                Layers::Inside,
+                |point| self.surface(texture, &transform, point, None),
+            ),
+            Fill::Block(id) => sample(structure, &transform, self.bounds(), Layers::Inside, |_| {
+                Some(Palette::new(id))
+            }),
+        }
+    }
+
+    /// The box in its own space, in units.
+    fn bounds(&self) -> ([f64; 3], [f64; 3]) {
+        let Dimensions3D { x, y, z } = self.dimensions;
+        let (x, y, z) = (x as f64, y as f64, z as f64);
+        ([1.0, 1.0 - y, 1.0 - z], [x + 1.0, 1.0, 1.0])
+    }
+
+    /// The block for the surface of the box at `point`, in its own space.
+    ///
+    /// Like on the grid, each face covers the outermost `layer` of blocks and
+    /// later faces win, but fall back to the nearest face for blocks that
+    /// rotation left a little deeper. Without a layer, the nearest face that
+    /// isn't see-through there is used.
+    fn surface(
+        &self,
+        texture: &Texture,
+        transform: &Transform,
+        [px, py, pz]: [f64; 3],
+        layer: Option<f64>,
+    ) -> Option<Palette> {
+        let Dimensions3D { x, y, z } = self.dimensions;
+        let (x, y, z) = (x as f64, y as f64, z as f64);
+        let bounds = self.bounds();
+        let exact = texture.scale == 1 && transform.scale.whole().is_some();
+        let half = 0.5 / transform.scale.min();
+        let (offsets, orientations) = self.oriented();
+
+        let (u, v) = (px.floor() as i64, py.floor() as i64);
+        let w = pz.floor() as i64;
+        let (dx, dy, dz) = (x as i64, y as i64, z as i64);
+
+        // Each face also has where the block is on it, in units, for
+        // textures that are sampled more finely than whole texels.
+        let mut faces = [
+            (
+                1.0 - pz,
+                offsets.front,
+                orientations.front,
+                (u - 1, -v),
+                [px - 1.0, 1.0 - py],
+                [0.0, 0.0, 1.0],
+            ),
+            (
+                x + 1.0 - px,
+                offsets.left,
+                orientations.left,
+                (-w, -v),
+                [1.0 - pz, 1.0 - py],
+                [1.0, 0.0, 0.0],
+            ),
+            (
+                px - 1.0,
+                offsets.right,
+                orientations.right,
+                (w - 1 + dz, -v),
+                [pz - 1.0 + z, 1.0 - py],
+                [-1.0, 0.0, 0.0],
+            ),
+            (
+                pz - bounds.0[2],
+                offsets.back,
+                orientations.back,
+                (dx - u, -v),
+                [x + 1.0 - px, 1.0 - py],
+                [0.0, 0.0, -1.0],
+            ),
+            (
+                py - bounds.0[1],
+                offsets.bottom,
+                orientations.bottom,
+                (u - 1, w - 1 + dz),
+                [px - 1.0, pz - 1.0 + z],
+                [0.0, -1.0, 0.0],
+            ),
+            (
+                1.0 - py,
+                offsets.top,
+                orientations.top,
+                (u - 1, w - 1 + dz),
+                [px - 1.0, pz - 1.0 + z],
+                [0.0, 1.0, 0.0],
+            ),
+        ];
+        let nearest = faces.iter().map(|f| f.0).fold(f64::MAX, f64::min);
+        if layer.is_none() {
+            faces.sort_by(|a, b| a.0.total_cmp(&b.0));
+        }
+
+        faces
+            .into_iter()
+            .filter(|f| layer.is_none_or(|layer| f.0 < layer || f.0 == nearest))
+            .find_map(|(_, origin, orientation, (tu, tv), point, normal)| {
+                let (width, height) = match normal {
+                    [_, 0.0, 0.0] => (dz, dy),
+                    [0.0, _, 0.0] => (dx, dz),
+                    _ => (dx, dy),
+                };
+                if !(0..width).contains(&tu) || !(0..height).contains(&tv) {
+                    return None;
+                }
+
+                let region = Region {
+                    origin,
+                    size: (width as u32, height as u32),
+                    orientation,
+                };
+                let pixel = match exact {
+                    true => texture.texel(&region, (tu as u32, tv as u32)),
+                    false => texture.sample(&region, point, [half; 2]),
+                };
+                let glows = texture.glows(&region, point);
+                texel_palette(texture, pixel, glows, transform, normal)
+            })
+    }
+
+    /// The texture offsets and orientations of each face, with the left and
+    /// right faces swapped and every face mirrored if the box is.
+    fn oriented(&self) -> (TextureOffsets, FaceOrientations) {
+        let (mut offsets, mut orientations) = (self.offsets, self.orientations);
+        if self.mirror {
+            std::mem::swap(&mut offsets.left, &mut offsets.right);
+            std::mem::swap(&mut orientations.left, &mut orientations.right);
+            for orientation in orientations.all_mut() {
+                orientation.mirror = !orientation.mirror;
+            }
+        }
+
+        (offsets, orientations)
+    }
+
     pub fn faces(&self) -> Vec<(Face, Brush)> {
+        let (offsets, orientations) = self.oriented();
         vec![
             // Top
             (
                 Face {
-                    x: self.offsets.top.0,
-                    y: self.offsets.top.1,
+                    x: offsets.top.0,
+                    y: offsets.top.1,
                     width: self.dimensions.x,
                     height: self.dimensions.z,
+                    orientation: orientations.top,
                     transform: Transform::new().translate(1, 0, 1 - (self.dimensions.z as i32)),
                 },
                 Brush::YPos,
@@ -33,10 +322,11 @@ impl Cuboid {
             // Bottom
             (
                 Face {
-                    x: self.offsets.bottom.0,
-                    y: self.offsets.bottom.1,
+                    x: offsets.bottom.0,
+                    y: offsets.bottom.1,
                     width: self.dimensions.x,
                     height: self.dimensions.z,
+                    orientation: orientations.bottom,
                     transform: Transform::new().translate(
                         1,
                         1 - (self.dimensions.y as i32),
@@ -48,10 +338,11 @@ impl Cuboid {
             // Back
             (
                 Face {
-                    x: self.offsets.back.0,
-                    y: self.offsets.back.1,
+                    x: offsets.back.0,
+                    y: offsets.back.1,
                     width: self.dimensions.x,
                     height: self.dimensions.y,
+                    orientation: orientations.back,
                     transform: Transform::new()
                         .rotate(Rotation::XPos)
                         .rotate(Rotation::YPos)
@@ -63,10 +354,11 @@ impl Cuboid {
             // Right
             (
                 Face {
-                    x: self.offsets.right.0,
-                    y: self.offsets.right.1,
+                    x: offsets.right.0,
+                    y: offsets.right.1,
                     width: self.dimensions.z,
                     height: self.dimensions.y,
+                    orientation: orientations.right,
                     transform: Transform::new()
                         .rotate(Rotation::XPos)
                         .rotate(Rotation::YNeg)
@@ -77,10 +369,11 @@ impl Cuboid {
             // Left
             (
                 Face {
-                    x: self.offsets.left.0,
-                    y: self.offsets.left.1,
+                    x: offsets.left.0,
+                    y: offsets.left.1,
                     width: self.dimensions.z,
                     height: self.dimensions.y,
+                    orientation: orientations.left,
                     transform: Transform::new()
                         .rotate(Rotation::XPos)
                         .rotate(Rotation::YPos)
@@ -91,10 +384,11 @@ impl Cuboid {
             // Front
             (
                 Face {
-                    x: self.offsets.front.0,
-                    y: self.offsets.front.1,
+                    x: offsets.front.0,
+                    y: offsets.front.1,
                     width: self.dimensions.x,
                     height: self.dimensions.y,
+                    orientation: orientations.front,
                     transform: Transform::new().rotate(Rotation::XPos).translate(1, 0, 0),
                 },
                 Brush::YPos,
@@ -107,10 +401,63 @@ impl Cuboid {
 pub struct Plane {
     pub dimensions: Dimensions2D,
     pub offset: (u32, u32),
+    pub priority: Priority,
     pub position: Transform,
 }
 
 impl Plane {
+    /// Draws the plane, where `others` are the boxes drawn with it that it
+    /// can be buried in.
+    pub fn draw(
+        &self,
+        structure: &mut Structure,
+        texture: &Texture,
+        pre_transform: &Transform,
+        others: &[&Volume],
+    ) {
+        let rank = |block| self.priority.rank(block, others);
+        if self.position.is_axis_aligned() {
+            let (face, brush) = self.face();
+            face.draw(
+                structure,
+                texture,
+                pre_transform,
+                &self.position,
+                brush,
+                rank,
+            );
+            return;
+        }
+
+        // Thicken the plane just enough that sampling can't see through it.
+        let transform = pre_transform.clone().then(&self.position);
+        let half = (0.5f64).max(0.87 / transform.scale.min());
+        let (width, height) = (self.dimensions.x as f64, self.dimensions.y as f64);
+        let bounds = ([0.0, 0.5 - half, 0.0], [width, 0.5 + half, height]);
+
+        let region = Region {
+            origin: self.offset,
+            size: (self.dimensions.x, self.dimensions.y),
+            orientation: UvOrientation::default(),
+        };
+        let layer = 1.0 / transform.scale.min();
+        let exact = texture.scale == 1 && transform.scale.whole().is_some();
+        sample(
+            structure,
+            &transform,
+            bounds,
+            Layers::Solid(&rank),
+            |[px, _, pz]| {
+                let pixel = match exact {
+                    true => texture.texel(&region, (px.floor() as u32, pz.floor() as u32)),
+                    false => texture.sample(&region, [px, pz], [layer / 2.0; 2]),
+                };
+                let glows = texture.glows(&region, [px, pz]);
+                texel_palette(texture, pixel, glows, &transform, [0.0, 1.0, 0.0])
+            },
+        );
+    }
+
     pub fn face(&self) -> (Face, Brush) {
         (
             Face {
@@ -118,6 +465,7 @@ impl Plane {
                 y: self.offset.1,
                 width: self.dimensions.x,
                 height: self.dimensions.y,
+                orientation: UvOrientation::default(),
                 transform: Transform::new(),
             },
             Brush::Full,
@@ -167,11 +515,88 @@ pub struct TextureOffsets {
     pub back: (u32, u32),
 }
 
+impl TextureOffsets {
+    /// The standard entity texture layout for a box of `dimensions` with its
+    /// top left corner at `(u, v)`.
+    pub fn from_box((u, v): (u32, u32), dimensions: Dimensions3D) -> Self {
+        let Dimensions3D { x: w, y: _, z: d } = dimensions;
+        Self {
+            top: (u + d, v),
+            bottom: (u + d + w, v),
+            right: (u, v + d),
+            left: (u + d + w, v + d),
+            front: (u + d, v + d),
+            back: (u + d + w + d, v + d),
+        }
+    }
+}
+
+/// How each face of a box turns its texture, in the same order as
+/// `TextureOffsets`.
+#[derive(Copy, Clone, Default)]
+pub struct FaceOrientations {
+    pub top: UvOrientation,
+    pub bottom: UvOrientation,
+    pub right: UvOrientation,
+    pub left: UvOrientation,
+    pub front: UvOrientation,
+    pub back: UvOrientation,
+}
+
+impl FaceOrientations {
+    fn all_mut(&mut self) -> [&mut UvOrientation; 6] {
+        [
+            &mut self.top,
+            &mut self.bottom,
+            &mut self.right,
+            &mut self.left,
+            &mut self.front,
+            &mut self.back,
+        ]
+    }
+}
+
+/// How a region of the texture is laid on a face: turned clockwise by
+/// `rotation` degrees, then mirrored left to right and flipped top to bottom.
+#[derive(Copy, Clone, Default)]
+pub struct UvOrientation {
+    pub rotation: u32,
+    pub mirror: bool,
+    pub flip: bool,
+}
+
+impl UvOrientation {
+    /// The size of the region a face of `(width, height)` reads, which is
+    /// turned on its side by quarter turns.
+    pub fn source_size(&self, (width, height): (u32, u32)) -> (u32, u32) {
+        match self.rotation {
+            90 | 270 => (height, width),
+            _ => (width, height),
+        }
+    }
+
+    /// Which texel of the region shows at `(x, y)` on a face of `size`.
+    pub fn texel(&self, (x, y): (u32, u32), size: (u32, u32)) -> (u32, u32) {
+        let (width, height) = size;
+        let x = if self.mirror { width - 1 - x } else { x };
+        let y = if self.flip { height - 1 - y } else { y };
+
+        let (source_width, source_height) = self.source_size(size);
+        match self.rotation {
+            90 => (y, source_height - 1 - x),
+            180 => (source_width - 1 - x, source_height - 1 - y),
+            270 => (source_width - 1 - y, x),
+            _ => (x, y),
+        }
+    }
+}
+
 pub struct Face {
     pub x: u32,
     pub y: u32,
     pub width: u32,
     pub height: u32,
+    pub orientation: UvOrientation,
     pub transform: Transform,
 }
 
@@ -179,10 +604,11 @@ impl Face {
     pub fn draw(
         &self,
         structure: &mut Structure,
-        image: &RgbaImage,
+        texture: &Texture,
         pre_transform: &Transform,
         post_transform: &Transform,
         brush: Brush,
+        rank: impl Fn([i32; 3]) -> i64,
     ) {
         let transform = pre_transform
             .clone()
@@ -193,23 +619,175 @@ impl Face {
         let net_rotation = transform.rotate_only(0, 1, 0);
         let normal = Direction::from_unit(net_rotation).expect("invalid rotation");
 
-        let view = image.view(self.x, self.y, self.width, self.height);
-        for (x, z, pixel) in view.pixels() {
-            if pixel.0[3] < 128 {
-                continue;
+        let region = Region {
+            origin: (self.x, self.y),
+            size: (self.width, self.height),
+            orientation: self.orientation,
+        };
+        if let (Some(scaling), 1) = (transform.scale.whole(), texture.scale) {
+            let offsets = brush.make_offsets([scaling; 3]);
+            let texels = (0..self.height).flat_map(|z| (0..self.width).map(move |x| (x, z)));
+            for (x, z) in texels {
+                let pixel = texture.texel(&region, (x, z));
+                let glows = texture.glows(&region, [x as f64 + 0.5, z as f64 + 0.5]);
+                let Some(palette) = texture.block(pixel, normal, glows) else {
+                    continue;
+                };
+
+                let coords = transform.apply(x as i32, 0, z as i32);
+                for (dx, dy, dz) in offsets.iter() {
+                    let block = [coords.0 + dx, coords.1 + dy, coords.2 + dz];
+                    structure.set_ranked(block, palette.clone(), rank(block));
+                }
+            }
+            return;
+        }
+
+        // Otherwise texels and blocks don't line up, so every block the face
+        // covers is sampled at the point of the face under its centre. Faces
+        // of flat boxes that have no texels are left out.
+        if self.width == 0 || self.height == 0 {
+            return;
+        }
+        let scale = transform.scale.0;
+        let corners = [[0.0; 3], [self.width as f64, 1.0, self.height as f64]];
+        let [a, b] = corners.map(|c| transform.apply_f64(c));
+        let ranges = [0, 1, 2].map(|i| transform.scale.blocks(i, a[i].min(b[i]), a[i].max(b[i])));
+        let extents = ranges.clone().map(|r| r.len() as i32);
+
+        // How far a block reaches across the face, which depends on the axis
+        // each side of the face ends up along.
+        let half = [[1.0, 0.0, 0.0], [0.0, 0.0, 1.0]].map(|side| {
+            let side = transform.rotate_only_f64(side);
+            let axis = (0..3)
+                .max_by(|&a, &b| side[a].abs().total_cmp(&side[b].abs()))
+                .unwrap_or(0);
+            0.5 / scale[axis]
+        });
+
+        let mut palettes = HashMap::new();
+        for (dx, dy, dz) in brush.make_offsets(extents) {
+            let block = [dx, dy, dz];
+            let block = [0, 1, 2].map(|i| ranges[i].start + block[i]);
+            let local = transform.invert_f64(transform.scale.centre(block));
+            let point = [local[0], local[2]];
+            let pixel = texture.sample(&region, point, half);
+            let glows = texture.glows(&region, point);
+            let palette = palettes
+                .entry((pixel.0, glows))
+                .or_insert_with(|| texture.block(pixel, normal, glows));
+            if let Some(palette) = palette {
+                structure.set_ranked(block, palette.clone(), rank(block));
             }
+        }
+    }
+}
 
-            let palette = find_closest(&pixel.0, normal);
+/// Which of the blocks in a box `sample` draws, and how they rank against
+/// blocks already there.
+enum Layers<'a> {
+    /// Only those on its surface, which keeps cuboids hollow like the
+    /// axis-aligned path does.
+    Surface(&'a dyn Fn([i32; 3]) -> i64),
+    Solid(&'a dyn Fn([i32; 3]) -> i64),
+    /// Only those under its surface, and only where nothing is drawn yet.
+    Inside,
+}
+
+/// Draws a primitive that doesn't line up with the grid by mapping every block
+/// near it back into the primitive's own space, where `bounds` is its box.
+fn sample(
+    structure: &mut Structure,
+    transform: &Transform,
+    bounds: ([f64; 3], [f64; 3]),
+    layers: Layers,
+    mut texel: impl FnMut([f64; 3]) -> Option<Palette>,
+) {
+    let (min, max) = bounds;
+    let inside = |p: [f64; 3]| (0..3).all(|i| p[i] >= min[i] && p[i] < max[i]);
+    let local = |b: [i32; 3]| transform.invert_f64(transform.scale.centre(b));
+
+    let ([x0, y0, z0], [x1, y1, z1]) = block_bounds(transform, bounds);
+    for x in x0..=x1 {
+        for y in y0..=y1 {
+            for z in z0..=z1 {
+                let point = local([x, y, z]);
+                if !inside(point) {
+                    continue;
+                }
 
-            let coords = transform.apply(x as i32, 0, z as i32);
-            for (dx, dy, dz) in brush.make_offsets(transform.scaling).into_iter() {
-                let offset_coords = (coords.0 + dx, coords.1 + dy, coords.2 + dz);
-                structure.set(offset_coords, palette.clone());
+                let neighbours = [
+                    [x - 1, y, z],
+                    [x + 1, y, z],
+                    [x, y - 1, z],
+                    [x, y + 1, z],
+                    [x, y, z - 1],
+                    [x, y, z + 1],
+                ];
+                let surface = !neighbours.into_iter().all(|n| inside(local(n)));
+                let skip = match layers {
+                    Layers::Surface(_) => !surface,
+                    Layers::Solid(_) => false,
+                    Layers::Inside => surface || structure.is_set((x, y, z)),
+                };
+                if skip {
+                    continue;
+                }
+
+                let Some(palette) = texel(point) else {
+                    continue;
+                };
+                match layers {
+                    Layers::Surface(rank) | Layers::Solid(rank) => {
+                        structure.set_ranked((x, y, z), palette, rank([x, y, z]))
+                    }
+                    Layers::Inside => structure.set((x, y, z), palette),
+                }
             }
         }
     }
 }
 
+/// The corners of the blocks around a box in the primitive's own space.
+fn block_bounds(transform: &Transform, (min, max): ([f64; 3], [f64; 3])) -> ([i32; 3], [i32; 3]) {
+    let scale = transform.scale.0;
+    let (mut low, mut high) = ([f64::MAX; 3], [f64::MIN; 3]);
+    for corner in 0..8 {
+        let point = [0, 1, 2].map(|i| if corner >> i & 1 == 0 { min[i] } else { max[i] });
+        let point = transform.apply_f64(point);
+        for i in 0..3 {
+            low[i] = low[i].min(point[i] * scale[i]);
+            high[i] = high[i].max(point[i] * scale[i]);
+        }
+    }
+
+    (low.map(|c| c.floor() as i32), high.map(|c| c.ceil() as i32))
+}
+
+/// Picks a block for a sampled texel, facing the nearest axis to `normal`.
+fn texel_palette(
+    texture: &Texture,
+    pixel: Rgba<u8>,
+    glows: bool,
+    transform: &Transform,
+    normal: [f64; 3],
+) -> Option<Palette> {
+    let normal = transform.rotate_only_f64(normal);
+    let axis = (0..3)
+        .max_by(|&a, &b| normal[a].abs().total_cmp(&normal[b].abs()))
+        .unwrap_or(1);
+    let mut unit = (0, 0, 0);
+    let sign = if normal[axis] < 0.0 { -1 } else { 1 };
+    match axis {
+        0 => unit.0 = sign,
+        1 => unit.1 = sign,
+        _ => unit.2 = sign,
+    }
+
+    let normal = Direction::from_unit(unit).ok()?;
+    texture.block(pixel, normal, glows)
+}
+
 #[allow(unused)]
 #[derive(Copy, Clone)]
 pub enum Brush {
@@ -223,23 +801,26 @@ pub enum Brush {
 }
 
 impl Brush {
-    pub fn make_offsets(&self, scaling: i32) -> Vec<(i32, i32, i32)> {
+    /// The blocks to fill in a box `extents` blocks big along each axis,
+    /// which is only its outermost layer on the brush's side.
+    pub fn make_offsets(&self, extents: [i32; 3]) -> Vec<(i32, i32, i32)> {
+        let [x, y, z] = extents;
         let xs: Vec<i32> = match self {
-            Brush::XPos => vec![scaling - 1],
+            Brush::XPos => vec![x - 1],
             Brush::XNeg => vec![0],
-            _ => (0..scaling).collect(),
+            _ => (0..x).collect(),
         };
 
         let ys: Vec<i32> = match self {
-            Brush::YPos => vec![scaling - 1],
+            Brush::YPos => vec![y - 1],
             Brush::YNeg => vec![0],
-            _ => (0..scaling).collect(),
+            _ => (0..y).collect(),
         };
 
         let zs: Vec<i32> = match self {
-            Brush::ZPos => vec![scaling - 1],
+            Brush::ZPos => vec![z - 1],
             Brush::ZNeg => vec![0],
-            _ => (0..scaling).collect(),
+            _ => (0..z).collect(),
         };
 
         let mut offsets = vec![];
@@ -1,66 +1,119 @@
-use crate::draw::Draw;
-use crate::drawing::{Brush, Face};
+use crate::draw::{Draw, Settings};
+use crate::drawing::{self, Face, Priority, UvOrientation};
 use crate::nbt::Structure;
-use crate::transform::{Plane, Rotation, Transform};
-use image::RgbaImage;
+use crate::texture::Texture;
+use crate::transform::{Transform, TransformStep};
+use crate::TEXTURE_DIR;
+use glob::Pattern;
+use once_cell::sync::Lazy;
+use serde::Deserialize;
+use std::fs::File;
 
 pub struct Item {
     pub texture: String,
     pub face: Face,
     pub position: Transform,
+    /// How many texels thick the item is.
+    pub thickness: u32,
 }
 
-impl Draw for Item {
-    fn draw(&self, structure: &mut Structure, scaling: u32, image: &RgbaImage) {
-        let pre_transform = Transform::with_scale(scaling);
-        self.face.draw(
-            structure,
-            image,
-            &pre_transform,
-            &self.position,
-            Brush::Full,
-        );
+impl Item {
+    /// Moves the item onto `anchor`, reading its texture from `offset` in an
+    /// atlas.
+    pub fn attach(mut self, anchor: &Transform, offset: (u32, u32)) -> Self {
+        self.face.x += offset.0;
+        self.face.y += offset.1;
+        self.position = self.position.then(anchor);
+        self
     }
 }
 
-pub fn get_item(name: &str) -> Option<Item> {
-    match name {
-        "diamond_axe" | "diamond_hoe" | "diamond_pickaxe" | "diamond_shovel" | "diamond_sword"
-        | "golden_axe" | "golden_hoe" | "golden_pickaxe" | "golden_shovel" | "golden_sword"
-        | "iron_axe" | "iron_hoe" | "iron_pickaxe" | "iron_shovel" | "iron_sword"
-        | "netherite_axe" | "netherite_hoe" | "netherite_pickaxe" | "netherite_shovel"
-        | "netherite_sword" | "stone_axe" | "stone_hoe" | "stone_pickaxe" | "stone_shovel"
-        | "stone_sword" | "wooden_axe" | "wooden_hoe" | "wooden_pickaxe" | "wooden_shovel"
-        | "wooden_sword" => Some(tool(name)),
-        "trident" => Some(trident()),
-        _ => None,
+impl Draw for Item {
+    fn draw(&self, structure: &mut Structure, settings: &Settings, texture: &Texture) {
+        // Like in the game, the sprite is extruded, so its sides take the
+        // colors of the pixels along its edges. Each layer is drawn as a
+        // plane so that items held at an angle are sampled.
+        let thickness = self.thickness.max(1) as i32;
+        let transform = Transform::with_scale(settings.scale);
+        for layer in 0..thickness {
+            let plane = drawing::Plane {
+                dimensions: [self.face.width, self.face.height].into(),
+                offset: (self.face.x, self.face.y),
+                // Held items go over the hand holding them.
+                priority: Priority {
+                    order: 0,
+                    overlay: true,
+                },
+                position: Transform::new()
+                    .translate(0, layer - (thickness - 1) / 2, 0)
+                    .then(&self.face.transform)
+                    .then(&self.position),
+            };
+            plane.draw(structure, texture, &transform, &[]);
+        }
     }
 }
 
-fn tool(name: &str) -> Item {
-    Item {
-        texture: format!("item/{name}.png"),
-        face: Face {
-            x: 0,
-            y: 0,
-            width: 16,
-            height: 16,
-            transform: Transform::new(),
-        },
-        position: Transform::new().rotate(Rotation::ZPos).mirror(Plane::XY),
-    }
+static ALL_ITEMS: Lazy<Vec<ItemStyle>> = Lazy::new(read_items);
+
+fn read_items() -> Vec<ItemStyle> {
+    let Ok(file) = File::open("items.json") else {
+        return vec![];
+    };
+    serde_json::from_reader(file).unwrap_or_default()
 }
 
-fn trident() -> Item {
-    Item {
-        texture: String::from("entity/trident.png"),
+/// How items whose names match `name` are drawn, taken from `items.json`.
+/// The first matching entry is used.
+#[derive(Deserialize)]
+struct ItemStyle {
+    /// Glob pattern of item names.
+    name: String,
+    /// Texture path, with `{name}` replaced by the item's name.
+    #[serde(default = "default_texture")]
+    texture: String,
+    /// Region of the texture to draw, the whole texture if not given.
+    #[serde(default)]
+    offset: [u32; 2],
+    dimensions: Option<[u32; 2]>,
+    position: Vec<TransformStep>,
+}
+
+fn default_texture() -> String {
+    String::from("item/{name}.png")
+}
+
+/// Looks up how to draw the item `name`, whose texture has `texture_scale`
+/// texels per unit.
+pub fn get_item(name: &str, texture_scale: f64) -> Option<Item> {
+    let style = ALL_ITEMS
+        .iter()
+        .find(|s| Pattern::new(&s.name).is_ok_and(|p| p.matches(name)))?;
+
+    let texture = style.texture.replace("{name}", name);
+    let [width, height] = match style.dimensions {
+        Some(dimensions) => dimensions,
+        None => {
+            let (width, height) =
+                image::image_dimensions(format!("{}/{}", TEXTURE_DIR, texture)).ok()?;
+            [width, height].map(|d| ((d as f64 / texture_scale).round() as u32).max(1))
+        }
+    };
+
+    Some(Item {
+        texture,
         face: Face {
-            x: 19,
-            y: 1,
-            width: 5,
-            height: 31,
+            x: style.offset[0],
+            y: style.offset[1],
+            width,
+            height,
+            orientation: UvOrientation::default(),
             transform: Transform::new(),
         },
-        position: Transform::new().rotate(Rotation::XPos),
-    }
+        position: style
+            .position
+            .iter()
+            .fold(Transform::new(), |pos, step| pos.step(*step)),
+        thickness: 1,
+    })
 }
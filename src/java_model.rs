@@ -0,0 +1,486 @@
+use crate::atlas::Atlas;
+use crate::drawing::{Cuboid, FaceOrientations, Plane, Primitive, Priority, TextureOffsets};
+use crate::model::Model;
+use crate::transform::{Axis, Rotation, Transform};
+use crate::{Error, BLOCKSTATE_DIR, MODEL_DIR, TEXTURE_DIR};
+use image::{imageops, RgbaImage};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs::File;
+
+/// The centre of a block, in the sixteenths models are measured in.
+const BLOCK_CENTRE: [f32; 3] = [8.0, 8.0, 8.0];
+
+/// A vanilla block or item model, as found in `models/block` and `models/item`.
+///
+/// Every face is cropped out of its texture and copied into an atlas, so the
+/// whole model can be drawn from a single image. Faces are resized to
+/// `texture_scale` texels per sixteenth of a block.
+pub struct JavaModel {
+    pub atlas: RgbaImage,
+    primitives: Vec<Primitive>,
+}
+
+impl JavaModel {
+    pub fn load(name: &str, texture_scale: u32) -> Result<Self, Error> {
+        let mut atlas = Atlas::new(texture_scale);
+        let primitives = load_primitives(name, None, &mut atlas)?;
+
+        Ok(Self {
+            atlas: atlas.into_image(),
+            primitives,
+        })
+    }
+
+    /// Loads the models the block `id`'s blockstate file picks for the given
+    /// `properties`, turned the way it says.
+    pub fn load_block(
+        id: &str,
+        properties: &[(String, String)],
+        texture_scale: u32,
+    ) -> Result<Self, Error> {
+        let id = strip_namespace(id);
+        let file = File::open(format!("{}/{}.json", BLOCKSTATE_DIR, id))?;
+        let blockstate: Blockstate = serde_json::from_reader(file)?;
+
+        let variants = blockstate.variants(properties);
+        if variants.is_empty() {
+            return Err(Error::UnsupportedModel(id.to_string()));
+        }
+
+        let mut atlas = Atlas::new(texture_scale);
+        let mut primitives = vec![];
+        for variant in variants.into_iter() {
+            // Blockstates turn models clockwise about the block's centre.
+            let rotation = Transform::new()
+                .rotate_by(Axis::X, -variant.x, BLOCK_CENTRE)
+                .rotate_by(Axis::Y, -variant.y, BLOCK_CENTRE);
+
+            for primitive in load_primitives(&variant.model, biome_color(id), &mut atlas)? {
+                primitives.push(match primitive {
+                    Primitive::Cuboid(mut cuboid) => {
+                        cuboid.position = cuboid.position.then(&rotation);
+                        Primitive::Cuboid(cuboid)
+                    }
+                    Primitive::Plane(mut plane) => {
+                        plane.position = plane.position.then(&rotation);
+                        Primitive::Plane(plane)
+                    }
+                });
+            }
+        }
+
+        Ok(Self {
+            atlas: atlas.into_image(),
+            primitives,
+        })
+    }
+}
+
+/// Adds the faces of the model `name` to `atlas`, multiplying those the game
+/// tints by `tint`, or leaving them as they are without one.
+fn load_primitives(
+    name: &str,
+    tint: Option<[u8; 3]>,
+    atlas: &mut Atlas,
+) -> Result<Vec<Primitive>, Error> {
+    let resolved = resolve(name)?;
+    let tinted = resolved
+        .elements
+        .iter()
+        .flatten()
+        .any(|e| e.faces.values().any(|f| f.tintindex.is_some()));
+    if tinted && tint.is_none() {
+        println!("Leaving tinted faces of {name} untinted, since its color isn't known");
+    }
+    match resolved.elements {
+        Some(elements) => elements
+            .iter()
+            .map(|e| element(e, &resolved.textures, tint, atlas))
+            .collect(),
+        None if resolved.generated => Ok(vec![generated(&resolved.textures, atlas)?]),
+        None => Err(Error::UnsupportedModel(name.to_string())),
+    }
+}
+
+/// The color the game tints a block's grass or foliage with, as in plains, for
+/// the blocks it's known for.
+fn biome_color(id: &str) -> Option<[u8; 3]> {
+    match id {
+        "grass_block" | "grass" | "short_grass" | "tall_grass" | "fern" | "large_fern"
+        | "potted_fern" | "sugar_cane" => Some([0x91, 0xbd, 0x59]),
+        "oak_leaves" | "jungle_leaves" | "acacia_leaves" | "dark_oak_leaves"
+        | "mangrove_leaves" | "vine" => Some([0x77, 0xab, 0x2f]),
+        "birch_leaves" => Some([0x80, 0xa7, 0x55]),
+        "spruce_leaves" => Some([0x61, 0x99, 0x61]),
+        "lily_pad" => Some([0x20, 0x80, 0x30]),
+        _ => None,
+    }
+}
+
+impl Model for JavaModel {
+    fn parts(&self) -> Vec<Primitive> {
+        self.primitives.clone()
+    }
+}
+
+/// A block's `blockstates` file, which picks models based on its properties.
+#[derive(Deserialize)]
+struct Blockstate {
+    #[serde(default)]
+    variants: HashMap<String, OneOrMore>,
+    #[serde(default)]
+    multipart: Vec<Case>,
+}
+
+impl Blockstate {
+    /// The first variant, by its properties, that the given ones don't rule
+    /// out, followed by every part whose conditions they satisfy.
+    fn variants(&self, properties: &[(String, String)]) -> Vec<&Variant> {
+        let mut keys = self.variants.keys().collect::<Vec<_>>();
+        keys.sort();
+        let key = keys.into_iter().find(|key| {
+            key.split(',').filter(|c| !c.is_empty()).all(|condition| {
+                let (name, value) = condition.split_once('=').unwrap_or((condition, ""));
+                properties
+                    .iter()
+                    .find(|(n, _)| n == name)
+                    .is_none_or(|(_, v)| v == value)
+            })
+        });
+
+        let mut variants = vec![];
+        if let Some(key) = key {
+            variants.push(self.variants[key].first());
+        }
+        for case in self.multipart.iter() {
+            if case.when.as_ref().is_none_or(|w| w.matches(properties)) {
+                variants.push(case.apply.first());
+            }
+        }
+
+        variants
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum OneOrMore {
+    One(Variant),
+    /// Picked from at random by the game, so the first is used.
+    More(Vec<Variant>),
+}
+
+impl OneOrMore {
+    fn first(&self) -> &Variant {
+        match self {
+            OneOrMore::One(variant) => variant,
+            OneOrMore::More(variants) => &variants[0],
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct Variant {
+    model: String,
+    #[serde(default)]
+    x: f32,
+    #[serde(default)]
+    y: f32,
+}
+
+#[derive(Deserialize)]
+struct Case {
+    when: Option<Condition>,
+    apply: OneOrMore,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Condition {
+    Or {
+        #[serde(rename = "OR")]
+        or: Vec<Condition>,
+    },
+    And {
+        #[serde(rename = "AND")]
+        and: Vec<Condition>,
+    },
+    /// Properties and the values, separated by `|`, any of which they can have.
+    Properties(HashMap<String, serde_json::Value>),
+}
+
+impl Condition {
+    /// Whether the given properties satisfy the condition. Properties that
+    /// weren't given satisfy nothing, so optional parts are left off.
+    fn matches(&self, properties: &[(String, String)]) -> bool {
+        match self {
+            Condition::Or { or } => or.iter().any(|c| c.matches(properties)),
+            Condition::And { and } => and.iter().all(|c| c.matches(properties)),
+            Condition::Properties(conditions) => conditions.iter().all(|(name, values)| {
+                let values = match values {
+                    serde_json::Value::String(values) => values.clone(),
+                    other => other.to_string(),
+                };
+                properties
+                    .iter()
+                    .find(|(n, _)| n == name)
+                    .is_some_and(|(_, v)| values.split('|').any(|value| value == v))
+            }),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct ModelFile {
+    parent: Option<String>,
+    #[serde(default)]
+    textures: HashMap<String, String>,
+    elements: Option<Vec<Element>>,
+}
+
+#[derive(Deserialize)]
+struct Element {
+    from: [f32; 3],
+    to: [f32; 3],
+    rotation: Option<ElementRotation>,
+    #[serde(default)]
+    faces: HashMap<FaceName, ElementFace>,
+}
+
+#[derive(Deserialize)]
+struct ElementRotation {
+    origin: [f32; 3],
+    axis: Axis,
+    angle: f32,
+}
+
+#[derive(Deserialize)]
+struct ElementFace {
+    uv: Option<[f32; 4]>,
+    texture: String,
+    #[serde(default)]
+    rotation: u32,
+    tintindex: Option<i32>,
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Deserialize)]
+enum FaceName {
+    #[serde(rename = "north")]
+    North,
+    #[serde(rename = "south")]
+    South,
+    #[serde(rename = "east")]
+    East,
+    #[serde(rename = "west")]
+    West,
+    #[serde(rename = "up")]
+    Up,
+    #[serde(rename = "down")]
+    Down,
+}
+
+struct Resolved {
+    textures: HashMap<String, String>,
+    elements: Option<Vec<Element>>,
+    generated: bool,
+}
+
+/// Follows the `parent` chain, merging texture variables and taking the
+/// elements of the closest model that defines any.
+fn resolve(name: &str) -> Result<Resolved, Error> {
+    let mut resolved = Resolved {
+        textures: HashMap::new(),
+        elements: None,
+        generated: false,
+    };
+
+    let mut next = Some(name.to_string());
+    while let Some(name) = next.take() {
+        let name = strip_namespace(&name);
+        match name {
+            "builtin/generated" => {
+                resolved.generated = true;
+                break;
+            }
+            "builtin/entity" => return Err(Error::UnsupportedModel(name.to_string())),
+            _ => {}
+        }
+
+        let file = File::open(format!("{}/{}.json", MODEL_DIR, name))?;
+        let model: ModelFile = serde_json::from_reader(file)?;
+
+        for (key, value) in model.textures.into_iter() {
+            resolved.textures.entry(key).or_insert(value);
+        }
+        if resolved.elements.is_none() {
+            resolved.elements = model.elements;
+        }
+        next = model.parent;
+    }
+
+    Ok(resolved)
+}
+
+fn strip_namespace(name: &str) -> &str {
+    name.strip_prefix("minecraft:").unwrap_or(name)
+}
+
+/// Resolves a `#variable` reference down to a texture and loads it.
+fn texture(reference: &str, textures: &HashMap<String, String>) -> Result<RgbaImage, Error> {
+    let mut reference = reference;
+    // Guards against variables that refer to each other.
+    for _ in 0..textures.len() + 1 {
+        let Some(variable) = reference.strip_prefix('#') else {
+            let path = format!("{}/{}.png", TEXTURE_DIR, strip_namespace(reference));
+            return Ok(image::open(path)?.into_rgba8());
+        };
+
+        match textures.get(variable) {
+            Some(value) => reference = value,
+            None => break,
+        }
+    }
+
+    Err(Error::UnresolvedTexture(reference.to_string()))
+}
+
+fn element(
+    element: &Element,
+    textures: &HashMap<String, String>,
+    tint: Option<[u8; 3]>,
+    atlas: &mut Atlas,
+) -> Result<Primitive, Error> {
+    let from = element.from.map(|c| c.round() as i32);
+    let to = element.to.map(|c| c.round() as i32);
+    let [x, y, z] = [0, 1, 2].map(|i| (to[i] - from[i]).max(1) as u32);
+
+    let mut face = |name, width, height| -> Result<(u32, u32), Error> {
+        let Some(face) = element.faces.get(&name) else {
+            return Ok(atlas.blank(width, height));
+        };
+
+        let mut image = face_texture(element, name, face, textures)?;
+        if let (Some(_), Some(color)) = (face.tintindex, tint) {
+            crate::tint(&mut image, color);
+        }
+        let scale = atlas.scale();
+        let image = imageops::resize(
+            &image,
+            width * scale,
+            height * scale,
+            imageops::FilterType::Nearest,
+        );
+        Ok(atlas.add(&image))
+    };
+
+    let offsets = TextureOffsets {
+        top: face(FaceName::Up, x, z)?,
+        bottom: face(FaceName::Down, x, z)?,
+        right: face(FaceName::West, z, y)?,
+        left: face(FaceName::East, z, y)?,
+        front: face(FaceName::South, x, y)?,
+        back: face(FaceName::North, x, y)?,
+    };
+
+    let mut position = Transform::new().translate(from[0] - 1, to[1] - 1, to[2] - 1);
+    if let Some(rotation) = &element.rotation {
+        position = position.rotate_by(rotation.axis, rotation.angle, rotation.origin);
+    }
+
+    Ok(Primitive::Cuboid(Cuboid {
+        dimensions: [x, y, z].into(),
+        offsets,
+        orientations: FaceOrientations::default(),
+        mirror: false,
+        fill: None,
+        priority: Priority::default(),
+        position,
+    }))
+}
+
+/// Crops a face out of its texture, oriented the way `Cuboid::faces` expects.
+fn face_texture(
+    element: &Element,
+    name: FaceName,
+    face: &ElementFace,
+    textures: &HashMap<String, String>,
+) -> Result<RgbaImage, Error> {
+    let image = texture(&face.texture, textures)?;
+    let [u1, v1, u2, v2] = face.uv.unwrap_or_else(|| default_uv(element, name));
+
+    // UVs are always in sixteenths, regardless of the texture's resolution.
+    let texel = image.width() as f32 / 16.0;
+    let left = (u1.min(u2) * texel).round() as u32;
+    let top = (v1.min(v2) * texel).round() as u32;
+    let width = (((u1 - u2).abs() * texel).round() as u32).max(1);
+    let height = (((v1 - v2).abs() * texel).round() as u32).max(1);
+
+    let mut cropped = imageops::crop_imm(&image, left, top, width, height).to_image();
+    if u1 > u2 {
+        imageops::flip_horizontal_in_place(&mut cropped);
+    }
+    if v1 > v2 {
+        imageops::flip_vertical_in_place(&mut cropped);
+    }
+
+    cropped = match face.rotation {
+        90 => imageops::rotate90(&cropped),
+        180 => imageops::rotate180(&cropped),
+        270 => imageops::rotate270(&cropped),
+        _ => cropped,
+    };
+
+    // The game's down faces run towards the north, but the bottom of a cuboid
+    // runs towards the south like its top.
+    if name == FaceName::Down {
+        imageops::flip_vertical_in_place(&mut cropped);
+    }
+
+    Ok(cropped)
+}
+
+/// The UVs the game derives from an element's bounds when a face has none.
+fn default_uv(element: &Element, name: FaceName) -> [f32; 4] {
+    let [fx, fy, fz] = element.from;
+    let [tx, ty, tz] = element.to;
+    match name {
+        FaceName::Up => [fx, fz, tx, tz],
+        FaceName::Down => [fx, 16.0 - tz, tx, 16.0 - fz],
+        FaceName::North => [16.0 - tx, 16.0 - ty, 16.0 - fx, 16.0 - fy],
+        FaceName::South => [fx, 16.0 - ty, tx, 16.0 - fy],
+        FaceName::West => [fz, 16.0 - ty, tz, 16.0 - fy],
+        FaceName::East => [16.0 - tz, 16.0 - ty, 16.0 - fz, 16.0 - fy],
+    }
+}
+
+/// Flat items are drawn as a single upright plane of their layered textures.
+fn generated(textures: &HashMap<String, String>, atlas: &mut Atlas) -> Result<Primitive, Error> {
+    let mut layers = vec![];
+    while let Some(layer) = textures.get(&format!("layer{}", layers.len())) {
+        layers.push(texture(layer, textures)?);
+    }
+
+    let Some(mut image) = layers.first().cloned() else {
+        return Err(Error::UnresolvedTexture(String::from("#layer0")));
+    };
+    for layer in layers.iter().skip(1) {
+        imageops::overlay(&mut image, layer, 0, 0);
+    }
+
+    // Like UVs, layers are sixteen units wide whatever their resolution.
+    let (width, height) = (16, (16 * image.height() / image.width()).max(1));
+    let scale = atlas.scale();
+    let image = imageops::resize(
+        &image,
+        width * scale,
+        height * scale,
+        imageops::FilterType::Nearest,
+    );
+    Ok(Primitive::Plane(Plane {
+        dimensions: [width, height].into(),
+        offset: atlas.add(&image),
+        priority: Priority::default(),
+        position: Transform::new().rotate(Rotation::XPos),
+    }))
+}
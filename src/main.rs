@@ -1,101 +1,485 @@
-use crate::draw::Draw;
+use crate::armor::{Armor, ArmorModel, Slot};
+use crate::atlas::Atlas;
+use crate::bedrock_model::BedrockModel;
+use crate::cull::{cull, Cull};
+use crate::draw::{Draw, Group, Settings};
+use crate::drawing::Fill;
 use crate::items::get_item;
+use crate::java_model::JavaModel;
 use crate::model::JsonModel;
 use crate::nbt::{DataVersion, Structure};
+use crate::pedestal::{Pedestal, Shape};
+use crate::scene::Scene;
+use crate::supports::{scaffolding, support, Supports};
+use crate::texture::{Filter, Texture};
+use crate::transform::Scale;
+use crate::validate::validate_models;
 use image::io::Reader as ImageReader;
-use image::ImageError;
+use image::{imageops, DynamicImage, ImageError, RgbaImage};
 use std::fs::File;
+use std::path::Path;
 
+mod armor;
+mod atlas;
+mod bedrock_model;
+mod cull;
 mod draw;
 mod drawing;
 mod items;
+mod java_model;
 mod materials;
 mod model;
 mod nbt;
+mod pedestal;
+mod scene;
+mod supports;
+mod texture;
 mod transform;
+mod validate;
 
 const SKIN_DIR: &str = ".";
 const TEXTURE_DIR: &str = "minecraft/1.20.1/assets/minecraft/textures";
+const MODEL_DIR: &str = "minecraft/1.20.1/assets/minecraft/models";
+const BLOCKSTATE_DIR: &str = "minecraft/1.20.1/assets/minecraft/blockstates";
 
 fn main() -> Result<(), Error> {
     let mut structure = Structure::new(DataVersion::Minecraft1_20_1);
 
-    let file = File::open("models.json")?;
-    let models: Vec<JsonModel> = serde_json::from_reader(file)?;
-
-    let Some(args) = Args::parse() else {
+    let Some(args) = Args::parse(std::env::args().skip(1)) else {
         println!("Invalid arguments");
         return Ok(());
     };
 
-    let (drawable, texture): (Box<dyn Draw>, String) = match args.what {
+    let built = match &args.what {
+        What::Scene { path } => compose(&mut structure, path)?,
+        _ => build(&mut structure, &args)?,
+    };
+    if !built {
+        return Ok(());
+    }
+    if structure.blocks.is_empty() {
+        println!("Nothing was drawn, so there's nothing to write");
+        return Ok(());
+    }
+    build_pedestal(&mut structure, &args);
+    if let Some(mode) = &args.cull {
+        cull(&mut structure, mode);
+    }
+
+    let mut f = File::create("output.nbt")?;
+    structure.normalize();
+    for supports in args.supports.iter() {
+        match supports {
+            Supports::Internal(block) => support(&mut structure, block),
+            Supports::Scaffold => {
+                if let Some(scaffolding) = scaffolding(&structure) {
+                    let mut f = File::create("scaffolding.nbt")?;
+                    scaffolding.write_out(&mut f)?;
+                }
+            }
+        }
+    }
+    structure.write_out(&mut f)?;
+
+    Ok(())
+}
+
+/// Draws what `args` describe into `structure`, returning `false` if it drew
+/// nothing, having said why.
+fn build(structure: &mut Structure, args: &Args) -> Result<bool, Error> {
+    let file = File::open("models.json")?;
+    let models: Vec<JsonModel> = serde_json::from_reader(file)?;
+
+    let texels = args.texels();
+    let mut emissive = args.emissive.clone().map(|path| (path, args.texture_scale));
+    let (drawable, image): (Box<dyn Draw>, RgbaImage) = match &args.what {
         What::Player {
             player,
             alternatives,
         } => {
             let Some(mut model) = models.into_iter().find(|m| m.name == "player") else {
                 println!("Player model not found");
-                return Ok(());
+                return Ok(false);
             };
 
-            for (part, alt) in alternatives.into_iter() {
+            if let Some(pose) = &args.pose {
+                if !model.use_pose(pose) {
+                    println!("Unknown pose \"{pose}\"");
+                    return Ok(false);
+                }
+            }
+
+            for (part, alt) in alternatives.iter() {
                 model.use_alternate(part, alt);
             }
 
+            if !use_variants(&mut model, &args.variants) {
+                return Ok(false);
+            }
+
             let texture = format!("{}/{}.png", SKIN_DIR, player);
-            (Box::new(model), texture)
+            let texture = resample(open_texture(&texture)?, args.texture_scale, texels);
+            dress(model, texture, args)?
         }
         What::Item { name } => {
-            let Some(item) = get_item(&name) else {
+            let Some(mut item) = get_item(name, args.texture_scale) else {
                 println!("Unsupported item \"{name}\"");
-                return Ok(());
+                return Ok(false);
             };
+            item.thickness = args.item_thickness;
 
             let texture = format!("{}/{}", TEXTURE_DIR, item.texture);
+            let texture = resample(open_texture(&texture)?, args.texture_scale, texels);
             (Box::new(item), texture)
         }
         What::Mob { name } => {
-            let Some(model) = models.into_iter().find(|m| m.name == name) else {
+            let Some(mut model) = models.into_iter().find(|m| &m.name == name) else {
                 println!("Unsupported mob \"{name}\"");
-                return Ok(());
+                return Ok(false);
             };
 
+            if let Some(pose) = &args.pose {
+                if !model.use_pose(pose) {
+                    println!("Unknown pose \"{pose}\"");
+                    return Ok(false);
+                }
+            }
+
+            if !use_variants(&mut model, &args.variants) {
+                return Ok(false);
+            }
+
+            // The game's own glow textures have a texel per unit, like its others.
+            if let (None, Some(path)) = (&emissive, &model.emissive) {
+                emissive = Some((format!("{}/{}", TEXTURE_DIR, path), 1.0));
+            }
+
             let texture = format!("{}/{}", TEXTURE_DIR, model.texture);
-            (Box::new(model), texture)
+            let texture = resample(open_texture(&texture)?, args.texture_scale, texels);
+            dress(model, texture, args)?
+        }
+        What::Validate { name } => {
+            validate_models(&models, name.as_deref());
+            return Ok(false);
+        }
+        What::Scene { .. } => {
+            println!("Scenes can't be part of a scene");
+            return Ok(false);
+        }
+        What::Model { name } => {
+            let mut model = JavaModel::load(name, texels)?;
+            let atlas = std::mem::take(&mut model.atlas);
+            (Box::new(model), atlas)
+        }
+        What::Block { id, properties } => {
+            let mut model = JavaModel::load_block(id, properties, texels)?;
+            let atlas = std::mem::take(&mut model.atlas);
+            (Box::new(model), atlas)
+        }
+        What::Geometry {
+            path,
+            texture,
+            identifier,
+        } => {
+            let mut model = BedrockModel::load(path, identifier.as_deref())?;
+            let texture = resample(open_texture(texture)?, args.texture_scale, texels);
+            let (width, height) = texture.dimensions();
+            model.check_uvs((width / texels, height / texels))?;
+
+            let mut atlas = Atlas::with_base(texture, texels);
+            model.blank_flat_faces(&mut atlas);
+            (Box::new(model), atlas.into_image())
         }
     };
 
-    let image = ImageReader::open(texture)?.decode()?;
-    let image = image.as_rgba8().ok_or(Error::NotRgba8)?;
+    let emissive = match emissive {
+        Some((path, scale)) => Some(resample(open_texture(&path)?, scale, texels)),
+        None => None,
+    };
+    let texture = Texture {
+        image,
+        scale: texels,
+        filter: args.texture_filter,
+        alpha_threshold: args.alpha_threshold,
+        glass: args.glass,
+        emissive,
+    };
+    let settings = Settings {
+        scale: args.model_scale,
+        fill: args.fill.clone(),
+    };
+    drawable.draw(structure, &settings, &texture);
 
-    drawable.draw(&mut structure, args.model_scale, image);
+    Ok(true)
+}
 
-    let mut f = File::create("output.nbt")?;
-    structure.normalize();
-    structure.write_out(&mut f)?;
+/// Builds each statue of a scene on its own, with its own pedestal, and
+/// places them together, returning `false` if one couldn't be built.
+fn compose(structure: &mut Structure, path: &str) -> Result<bool, Error> {
+    let scene = Scene::load(path)?;
+    if scene.statues.is_empty() {
+        println!("The scene has no statues");
+        return Ok(false);
+    }
 
-    Ok(())
+    // Every statue is checked before any is built, which can take a while.
+    let mut statues = vec![];
+    for (i, placement) in scene.statues.iter().enumerate() {
+        let n = i + 1;
+        let Some(args) = Args::parse(placement.args.iter().cloned()) else {
+            println!("Invalid arguments for statue {n}");
+            return Ok(false);
+        };
+        if args.cull.is_some() || !args.supports.is_empty() {
+            println!("Statue {n} can't use --cull or --supports, which are for the whole scene");
+            return Ok(false);
+        }
+        if placement.turns().is_none() {
+            println!("Statue {n} must be turned a multiple of 90 degrees");
+            return Ok(false);
+        }
+        statues.push((placement, args));
+    }
+
+    for (i, (placement, args)) in statues.iter().enumerate() {
+        let n = i + 1;
+        let mut statue = Structure::new(structure.data_version);
+        if !build(&mut statue, args)? {
+            return Ok(false);
+        }
+        build_pedestal(&mut statue, args);
+
+        let overlaps = placement.place(structure, &statue);
+        if overlaps > 0 {
+            println!("Statue {n} overlaps earlier ones by {overlaps} blocks, which keep theirs");
+        }
+    }
+
+    Ok(true)
+}
+
+fn build_pedestal(structure: &mut Structure, args: &Args) {
+    if let Some(shape) = args.pedestal {
+        let pedestal = Pedestal {
+            shape,
+            block: args.pedestal_block.clone(),
+            height: args.pedestal_height,
+            name_plate: args.name_plate.clone(),
+        };
+        pedestal.build(structure);
+    }
+}
+
+fn use_variants(model: &mut JsonModel, variants: &[String]) -> bool {
+    for variant in variants.iter() {
+        if !model.use_variant(variant) {
+            println!("Unknown variant \"{variant}\"");
+            return false;
+        }
+    }
+
+    true
+}
+
+/// Gives the model its extra textures and inflated layers, puts armor on it
+/// and attaches items to its anchors, adding their textures to its own.
+fn dress(
+    mut model: JsonModel,
+    texture: RgbaImage,
+    args: &Args,
+) -> Result<(Box<dyn Draw>, RgbaImage), Error> {
+    let texels = args.texels();
+    let mut texture = texture;
+    for overlay in model.overlays.iter() {
+        let overlay = open_texture(&format!("{}/{}", TEXTURE_DIR, overlay))?;
+        imageops::overlay(&mut texture, &resample(overlay, 1.0, texels), 0, 0);
+    }
+
+    let paths = model
+        .textures
+        .iter()
+        .map(|(name, path)| (name.clone(), format!("{}/{}", TEXTURE_DIR, path)))
+        .collect::<Vec<_>>();
+    let mut textures = vec![];
+    for (name, path) in paths.iter().chain(args.textures.iter()) {
+        // Extra textures are taken to be the game's own size, unless the
+        // model says how wide they are.
+        let texture = open_texture(path)?;
+        let scale = model
+            .texture_sizes
+            .get(name.as_str())
+            .map_or(1.0, |&(width, _)| texture.width() as f64 / width as f64);
+        let mut texture = resample(texture, scale, texels);
+        if let Some(color) = model.tints.get(name) {
+            tint(&mut texture, *color);
+        }
+        textures.push((name, texture));
+    }
+
+    // Parts outside their texture would otherwise panic while drawing.
+    let units = |texture: &RgbaImage| {
+        let (width, height) = texture.dimensions();
+        (width / texels, height / texels)
+    };
+    let sizes = textures
+        .iter()
+        .map(|(name, texture)| (name.to_string(), units(texture)))
+        .collect();
+    let errors = model
+        .validate(Some(units(&texture)), &sizes)
+        .into_iter()
+        .filter(|p| p.is_error())
+        .collect::<Vec<_>>();
+    if !errors.is_empty() {
+        for error in errors.iter() {
+            println!("{error}");
+        }
+        return Err(Error::InvalidModel(model.name));
+    }
+
+    let mut atlas = Atlas::with_base(texture, texels);
+    for (name, texture) in textures.iter() {
+        let offset = atlas.add(texture);
+        model.use_texture(name, offset);
+    }
+    model.inflate_parts(&mut atlas);
+
+    let armor = ArmorModel::new(&model, &args.armor, &mut atlas)?;
+    let mut items: Vec<Box<dyn Draw>> = vec![];
+    for (anchor_name, item_name) in args.held.iter() {
+        let Some(anchor) = model.anchor(anchor_name) else {
+            println!("Skipping unknown anchor \"{anchor_name}\"");
+            continue;
+        };
+        let Some(mut item) = get_item(item_name, 1.0) else {
+            println!("Skipping unsupported item \"{item_name}\"");
+            continue;
+        };
+        item.thickness = args.item_thickness;
+
+        let texture = open_texture(&format!("{}/{}", TEXTURE_DIR, item.texture))?;
+        let offset = atlas.add(&resample(texture, 1.0, texels));
+        items.push(Box::new(item.attach(&anchor, offset)));
+    }
+
+    items.insert(0, Box::new(armor));
+    items.insert(0, Box::new(model));
+    Ok((Box::new(Group(items)), atlas.into_image()))
+}
+
+fn open_texture(path: &str) -> Result<RgbaImage, Error> {
+    match ImageReader::open(path)?.decode()? {
+        DynamicImage::ImageRgba8(image) => Ok(image),
+        _ => Err(Error::NotRgba8),
+    }
+}
+
+/// Whether `id` is one of the game's blocks, going by the blockstates that
+/// block mode reads.
+fn is_block(id: &str) -> bool {
+    let name = id.strip_prefix("minecraft:").unwrap_or(id);
+    Path::new(&format!("{}/{}.json", BLOCKSTATE_DIR, name)).is_file()
+}
+
+/// Like `is_block`, but saying so if it isn't.
+fn known_block(id: &str) -> bool {
+    let known = is_block(id);
+    if !known {
+        println!("Unknown block \"{id}\"");
+    }
+    known
+}
+
+/// Resizes `image`, which has `scale` texels per unit, to have `texels` per
+/// unit instead, repeating texels to enlarge it.
+fn resample(image: RgbaImage, scale: f64, texels: u32) -> RgbaImage {
+    if scale == texels as f64 {
+        return image;
+    }
+    let [width, height] = [image.width(), image.height()]
+        .map(|d| ((d as f64 / scale).round() as u32).max(1) * texels);
+    imageops::resize(&image, width, height, imageops::FilterType::Nearest)
+}
+
+/// Multiplies the colors of `image` by `color`, like the game does for dyed or
+/// biome-colored textures.
+fn tint(image: &mut RgbaImage, color: [u8; 3]) {
+    for pixel in image.pixels_mut() {
+        for (channel, tint) in pixel.0.iter_mut().zip(color) {
+            *channel = (*channel as u32 * tint as u32 / 255) as u8;
+        }
+    }
 }
 
 struct Args {
-    model_scale: u32,
-    texture_scale: u32,
+    /// Blocks per model unit along each axis.
+    model_scale: Scale,
+    fill: Option<Fill>,
+    /// What happens to blocks hidden inside the statue.
+    cull: Option<Cull>,
+    pedestal: Option<Shape>,
+    pedestal_block: String,
+    pedestal_height: i32,
+    /// Text for a sign on the front of the pedestal.
+    name_plate: Option<String>,
+    /// How blocks with nothing under them are held up, in order.
+    supports: Vec<Supports>,
+    /// Texels per model unit in the texture drawn, such as 2 for 128x128
+    /// skins or 0.5 for 32x32 ones. Every other texture is the game's own,
+    /// with one.
+    texture_scale: f64,
+    texture_filter: Filter,
+    alpha_threshold: u8,
+    glass: bool,
+    /// A texture marking the texels that glow, laid out like the one drawn.
+    emissive: Option<String>,
+    /// How many texels thick items are extruded.
+    item_thickness: u32,
+    pose: Option<String>,
+    variants: Vec<String>,
+    held: Vec<(String, String)>,
+    armor: Vec<Armor>,
+    /// Extra textures by name, such as a cape.
+    textures: Vec<(String, String)>,
     what: What,
 }
 
 impl Args {
-    fn parse() -> Option<Self> {
-        let mut args = std::env::args().peekable();
-        args.next(); // Skip program name
+    /// Texels per unit that every texture is resampled to before drawing,
+    /// enough to keep all the detail of the one drawn.
+    fn texels(&self) -> u32 {
+        self.texture_scale.ceil() as u32
+    }
+
+    /// Parses arguments, not counting the program name.
+    fn parse(args: impl Iterator<Item = String>) -> Option<Self> {
+        let mut args = args.peekable();
 
         let mut x = Args {
-            model_scale: 1,
-            texture_scale: 1,
+            model_scale: Scale::uniform(1.0),
+            fill: None,
+            cull: None,
+            pedestal: None,
+            pedestal_block: "smooth_stone".to_string(),
+            pedestal_height: 2,
+            name_plate: None,
+            supports: vec![],
+            texture_scale: 1.0,
+            texture_filter: Filter::Average,
+            alpha_threshold: 128,
+            glass: false,
+            emissive: None,
+            item_thickness: 1,
+            pose: None,
+            variants: vec![],
+            held: vec![],
+            armor: vec![],
+            textures: vec![],
             what: match args.next()?.as_str() {
                 "player" => {
                     let player = args.next()?;
                     let mut alternatives = vec![];
-                    while args.peek().map_or(false, |a| !a.starts_with("--")) {
+                    while args.peek().is_some_and(|a| !a.starts_with("--")) {
                         let arg = args.next().unwrap();
                         let mut parts = arg.splitn(2, ':');
                         let part_name = parts.next()?.to_string();
@@ -109,15 +493,122 @@ impl Args {
                 }
                 "item" => What::Item { name: args.next()? },
                 "mob" => What::Mob { name: args.next()? },
+                "model" => What::Model { name: args.next()? },
+                "validate" => What::Validate {
+                    name: args.next_if(|a| !a.starts_with("--")),
+                },
+                "block" => {
+                    let id = args.next()?;
+                    let mut properties = vec![];
+                    while args.peek().is_some_and(|a| !a.starts_with("--")) {
+                        let arg = args.next().unwrap();
+                        let (name, value) = arg.split_once('=')?;
+                        properties.push((name.to_string(), value.to_string()));
+                    }
+                    What::Block { id, properties }
+                }
+                "scene" => What::Scene { path: args.next()? },
+                "geo" => What::Geometry {
+                    path: args.next()?,
+                    texture: args.next()?,
+                    identifier: args.next_if(|a| !a.starts_with("--")),
+                },
                 _ => return None,
             },
         };
 
         for a in args {
             if let Some(n) = a.strip_prefix("--model-scale=") {
-                x.model_scale = n.parse().ok()?;
+                x.model_scale = Scale::parse(n)?;
+            } else if let Some(n) = a.strip_prefix("--fill=") {
+                let Some(fill) = Fill::from_name(n) else {
+                    println!("Unknown fill \"{n}\"");
+                    return None;
+                };
+                if let Fill::Block(block) = &fill {
+                    if !known_block(block) {
+                        return None;
+                    }
+                }
+                x.fill = Some(fill);
+            } else if let Some(n) = a.strip_prefix("--cull=") {
+                let Some(cull) = Cull::from_name(n) else {
+                    println!("Unknown cull mode \"{n}\"");
+                    return None;
+                };
+                if let Cull::Replace(block) = &cull {
+                    if !known_block(block) {
+                        return None;
+                    }
+                }
+                x.cull = Some(cull);
+            } else if let Some(n) = a.strip_prefix("--pedestal=") {
+                x.pedestal = Some(Shape::from_name(n)?);
+            } else if let Some(n) = a.strip_prefix("--pedestal-block=") {
+                if !known_block(n) {
+                    return None;
+                }
+                x.pedestal_block = n.to_string();
+            } else if let Some(n) = a.strip_prefix("--pedestal-height=") {
+                x.pedestal_height = n.parse().ok().filter(|&n| n > 0)?;
+            } else if let Some(n) = a.strip_prefix("--name-plate=") {
+                x.name_plate = Some(n.to_string());
+            } else if let Some(n) = a.strip_prefix("--supports=") {
+                let Some(supports) = Supports::from_name(n) else {
+                    println!("Unknown supports mode \"{n}\"");
+                    return None;
+                };
+                if let Supports::Internal(block) = &supports {
+                    if !known_block(block) {
+                        return None;
+                    }
+                }
+                x.supports.push(supports);
             } else if let Some(n) = a.strip_prefix("--texture-scale=") {
-                x.texture_scale = n.parse().ok()?;
+                x.texture_scale = n.parse().ok().filter(|&n: &f64| n > 0.0 && n.is_finite())?;
+            } else if let Some(n) = a.strip_prefix("--alpha-threshold=") {
+                x.alpha_threshold = n.parse().ok()?;
+            } else if a == "--glass" {
+                x.glass = true;
+            } else if let Some(n) = a.strip_prefix("--emissive=") {
+                x.emissive = Some(n.to_string());
+            } else if let Some(n) = a.strip_prefix("--texture-filter=") {
+                x.texture_filter = Filter::from_name(n)?;
+            } else if let Some(n) = a.strip_prefix("--item-thickness=") {
+                x.item_thickness = n.parse().ok()?;
+            } else if let Some(n) = a.strip_prefix("--pose=") {
+                x.pose = Some(n.to_string());
+            } else if let Some(n) = a.strip_prefix("--variant=") {
+                x.variants.push(n.to_string());
+            } else if let Some(n) = a.strip_prefix("--hold=") {
+                let (anchor, item) = n.split_once(':')?;
+                x.held.push((anchor.to_string(), item.to_string()));
+            } else if let Some(n) = a.strip_prefix("--cape=") {
+                let path = format!("{}/{}.png", SKIN_DIR, n);
+                x.textures.push(("cape".to_string(), path));
+            } else if a == "--elytra" {
+                let path = format!("{}/entity/elytra.png", TEXTURE_DIR);
+                x.textures.push(("elytra".to_string(), path));
+            } else if let Some(n) = a.strip_prefix("--elytra=") {
+                // Capes include a matching elytra texture.
+                let path = format!("{}/{}.png", SKIN_DIR, n);
+                x.textures.push(("elytra".to_string(), path));
+            } else if let Some(n) = a.strip_prefix("--armor=") {
+                // Sets every slot, so that single slots can then be changed.
+                for slot in Slot::ALL {
+                    x.armor.retain(|a| a.slot != slot);
+                    x.armor.push(Armor::parse(slot, n)?);
+                }
+            } else if let Some((slot, n)) = a
+                .strip_prefix("--")
+                .and_then(|a| a.split_once('='))
+                .and_then(|(slot, n)| Some((Slot::from_name(slot)?, n)))
+            {
+                x.armor.retain(|a| a.slot != slot);
+                x.armor.push(Armor::parse(slot, n)?);
+            } else {
+                println!("Unknown argument \"{a}\"");
+                return None;
             }
         }
 
@@ -136,6 +627,26 @@ enum What {
     Mob {
         name: String,
     },
+    Model {
+        name: String,
+    },
+    /// Checks `models.json` instead of drawing anything.
+    Validate {
+        name: Option<String>,
+    },
+    Block {
+        id: String,
+        properties: Vec<(String, String)>,
+    },
+    Geometry {
+        path: String,
+        texture: String,
+        identifier: Option<String>,
+    },
+    /// Several statues placed together, described by a JSON file.
+    Scene {
+        path: String,
+    },
 }
 
 #[allow(dead_code)]
@@ -145,6 +656,9 @@ enum Error {
     Io(std::io::Error),
     Serde(serde_json::Error),
     NotRgba8,
+    UnsupportedModel(String),
+    UnresolvedTexture(String),
+    InvalidModel(String),
 }
 
 impl From<ImageError> for Error {
@@ -18,9 +18,23 @@ struct Block {
     texturing: Texturing,
     part: Option<String>,
     avg_color: [u8; 3],
+    /// Whether the block can be seen through, which keeps it for texels
+    /// that can be too.
+    #[serde(default)]
+    translucent: bool,
+    /// Whether the block gives off light, which keeps it for texels that
+    /// glow.
+    #[serde(default)]
+    emissive: bool,
 }
 
 impl Block {
+    /// Whether the color is of the block lit, like a powered redstone lamp,
+    /// which it only stays as until something updates it.
+    fn is_lit(&self) -> bool {
+        self.part.as_deref() == Some("on")
+    }
+
     fn try_orient(&self, texturing: Texturing, normal: Direction) -> Option<Orient> {
         match texturing {
             Texturing::Uniform => Some(Orient::AsIs),
@@ -139,11 +153,41 @@ impl Orient {
     }
 }
 
+/// The see-through block closest to a color, ignoring its alpha.
+pub fn find_translucent(color: &[u8]) -> Palette {
+    let closest = ALL_BLOCKS
+        .iter()
+        .filter(|b| b.translucent)
+        .min_by_key(|b| b.distance(color));
+    match closest {
+        Some(block) => Palette::new(&block.block_id),
+        None => Palette::new("glass"),
+    }
+}
+
+/// Whether blocks behind a block can be seen through it.
+pub fn is_see_through(block_id: &str) -> bool {
+    block_id.ends_with("glass")
+        || block_id.ends_with("glass_pane")
+        || block_id.ends_with("leaves")
+        || block_id.ends_with("sign")
+        || matches!(block_id, "ice" | "honey_block" | "slime_block")
+}
+
 pub fn find_closest(color: &[u8], normal: Direction) -> Palette {
+    closest(color, normal, |b| !b.translucent && !b.is_lit())
+}
+
+/// The light-giving block closest to a color, for texels that glow.
+pub fn find_emissive(color: &[u8], normal: Direction) -> Palette {
+    closest(color, normal, |b| b.emissive)
+}
+
+fn closest(color: &[u8], normal: Direction, usable: impl Fn(&Block) -> bool) -> Palette {
     let mut closest_dist = i32::MAX;
     let mut palette = Palette::new("air");
 
-    for block in ALL_BLOCKS.iter() {
+    for block in ALL_BLOCKS.iter().filter(|b| usable(b)) {
         let Some(orient) = block.try_orient(block.texturing, normal) else {
             continue;
         };
@@ -154,7 +198,10 @@ pub fn find_closest(color: &[u8], normal: Direction) -> Palette {
         }
 
         closest_dist = dist;
-        let p = Palette::new(&block.block_id);
+        let mut p = Palette::new(&block.block_id);
+        if block.is_lit() {
+            p = p.with_property("lit", "true");
+        }
         if let Orient::With { name, value } = orient {
             palette = p.with_property(name, value);
         } else {
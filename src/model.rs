@@ -1,8 +1,15 @@
-use crate::draw::Draw;
-use crate::drawing::{Cuboid, Plane, Primitive, TextureOffsets};
+use crate::armor::ArmorLayer;
+use crate::atlas::Atlas;
+use crate::draw::{Draw, Settings};
+use crate::drawing::{
+    Cuboid, Dimensions2D, Dimensions3D, FaceOrientations, Fill, Plane, Primitive, Priority,
+    TextureOffsets, UvOrientation,
+};
+use crate::is_block;
 use crate::nbt::Structure;
-use crate::transform::{Transform, TransformStep};
-use image::RgbaImage;
+use crate::texture::Texture;
+use crate::transform::{Transform, TransformStep, Turn};
+use crate::validate::{Problem, Report};
 use serde::Deserialize;
 use std::collections::HashMap;
 
@@ -11,19 +18,47 @@ pub trait Model {
 }
 
 impl<T: Model> Draw for T {
-    fn draw(&self, structure: &mut Structure, scaling: u32, image: &RgbaImage) {
-        let transform = Transform::with_scale(scaling);
-        for part in self.parts().into_iter() {
-            match part {
-                Primitive::Cuboid(cuboid) => {
-                    for (face, brush) in cuboid.faces().into_iter() {
-                        face.draw(structure, image, &transform, &cuboid.position, brush);
-                    }
-                }
-                Primitive::Plane(plane) => {
-                    let (face, brush) = plane.face();
-                    face.draw(structure, image, &transform, &plane.position, brush);
+    fn draw(&self, structure: &mut Structure, settings: &Settings, texture: &Texture) {
+        let transform = Transform::with_scale(settings.scale);
+        let parts = self.parts();
+
+        // Insides go first so surfaces always cover them, and smaller boxes
+        // before larger ones so layers over a part leave its inside to it.
+        let mut cuboids = parts
+            .iter()
+            .filter_map(|p| match p {
+                Primitive::Cuboid(cuboid) => Some(cuboid),
+                Primitive::Plane(_) => None,
+            })
+            .collect::<Vec<_>>();
+        cuboids.sort_by_key(|c| c.dimensions.x * c.dimensions.y * c.dimensions.z);
+        for cuboid in cuboids {
+            if let Some(fill) = cuboid.fill.as_ref().or(settings.fill.as_ref()) {
+                cuboid.fill(structure, texture, &transform, fill);
+            }
+        }
+
+        // Layers over other parts are mostly see-through, so nothing is
+        // buried in them.
+        let volumes = parts
+            .iter()
+            .map(|p| match p {
+                Primitive::Cuboid(cuboid) if !cuboid.priority.overlay => {
+                    Some(cuboid.volume(&transform))
                 }
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        for (i, part) in parts.iter().enumerate() {
+            let others = volumes
+                .iter()
+                .enumerate()
+                .filter(|&(j, _)| j != i)
+                .filter_map(|(_, v)| v.as_ref())
+                .collect::<Vec<_>>();
+            match part {
+                Primitive::Cuboid(cuboid) => cuboid.draw(structure, texture, &transform, &others),
+                Primitive::Plane(plane) => plane.draw(structure, texture, &transform, &others),
             }
         }
     }
@@ -36,41 +71,440 @@ pub struct JsonModel {
     pub parts: Vec<Part>,
     #[serde(default)]
     alternates: HashMap<String, String>,
+    #[serde(default)]
+    poses: Vec<Pose>,
+    #[serde(default)]
+    anchors: Vec<Anchor>,
+    #[serde(default)]
+    pub armor: Vec<ArmorLayer>,
+    /// Extra textures the model's parts use, by name, relative to the
+    /// texture directory.
+    #[serde(default)]
+    pub textures: HashMap<String, String>,
+    /// The size of the model's texture, for checking UVs without it at hand.
+    pub texture_size: Option<(u32, u32)>,
+    /// The sizes of the extra textures the model's parts use, by name.
+    #[serde(default)]
+    pub texture_sizes: HashMap<String, (u32, u32)>,
+    /// A texture laid out like the model's own whose drawn texels mark the
+    /// ones that glow, like an enderman's eyes, relative to the texture
+    /// directory.
+    pub emissive: Option<String>,
+    #[serde(default)]
+    variants: Vec<Variant>,
+    /// Textures drawn over the model's own by the variants in use.
+    #[serde(skip)]
+    pub overlays: Vec<String>,
+    /// Colors the variants in use multiply extra textures by.
+    #[serde(skip)]
+    pub tints: HashMap<String, [u8; 3]>,
+    /// Extra rotations from the pose in use, applied after alternates.
+    #[serde(skip)]
+    rotations: HashMap<String, Vec<Turn>>,
+    /// Where each extra texture the model's parts use was put in its atlas.
+    #[serde(skip)]
+    texture_origins: HashMap<String, (u32, u32)>,
 }
 
 impl JsonModel {
+    /// Applies a named pose, returning `false` if the model has none by that
+    /// name. Alternates chosen afterwards override the pose for their part.
+    pub fn use_pose(&mut self, pose_name: &str) -> bool {
+        let Some(pose) = self.poses.iter().find(|p| p.name == pose_name) else {
+            return false;
+        };
+
+        let alternates = pose.alternates.clone();
+        self.rotations = pose.rotate.clone();
+        for (part_name, alt_name) in alternates.into_iter() {
+            self.set_alternate(part_name, alt_name);
+        }
+
+        true
+    }
+
+    /// Applies a named variant, returning `false` if the model has none by
+    /// that name. Later variants are drawn over earlier ones.
+    pub fn use_variant(&mut self, variant_name: &str) -> bool {
+        let Some(variant) = self.variants.iter().find(|v| v.name == variant_name) else {
+            return false;
+        };
+
+        if let Some(texture) = &variant.texture {
+            self.texture = texture.clone();
+        }
+        for (name, path) in variant.textures.iter() {
+            self.textures.insert(name.clone(), path.clone());
+        }
+        self.overlays.extend(variant.overlays.iter().cloned());
+        self.tints.extend(variant.tint.iter().map(|(n, c)| (n.clone(), *c)));
+
+        true
+    }
+
     pub fn use_alternate(&mut self, part_name: impl ToString, alt_name: impl ToString) {
         let part_name = part_name.to_string();
-        let Some(alternates) = self.parts.iter().find_map(|p| match p {
-            Part::Cuboid(p) if p.name == part_name => Some(&p.alternates),
-            Part::Flat(p) if p.name == part_name => Some(&p.alternates),
-            _ => None,
-        }) else {
+        self.rotations.remove(&part_name);
+        self.set_alternate(part_name, alt_name.to_string());
+    }
+
+    /// Records where the extra texture called `name` is in the model's
+    /// atlas. Parts using a texture are only drawn once it has been given.
+    pub fn use_texture(&mut self, name: impl ToString, offset: (u32, u32)) {
+        self.texture_origins.insert(name.to_string(), offset);
+    }
+
+    /// Stretches the faces of parts that are inflated into `atlas`, which
+    /// must already hold every texture the model uses.
+    pub fn inflate_parts(&mut self, atlas: &mut Atlas) {
+        let source = atlas.image().clone();
+        let scale = atlas.scale();
+        for part in self.parts.iter_mut() {
+            let Part::Cuboid(cuboid) = part else {
+                continue;
+            };
+            if cuboid.inflate == 0 {
+                continue;
+            }
+
+            let origin = match &cuboid.texture {
+                Some(texture) => match self.texture_origins.get(texture) {
+                    Some(origin) => *origin,
+                    None => continue,
+                },
+                None => (0, 0),
+            };
+            let offsets = cuboid.offsets(origin);
+            let orientations = cuboid.orientations();
+            let dimensions = cuboid.dimensions.into();
+            cuboid.stretched = Some(atlas.add_stretched(
+                &source,
+                scale,
+                offsets,
+                orientations,
+                dimensions,
+                cuboid.inflate,
+            ));
+        }
+    }
+
+    fn set_alternate(&mut self, part_name: String, alt_name: String) {
+        let Some(part) = self.parts.iter().find(|p| p.name() == part_name) else {
             return;
         };
 
-        let alt_name = alt_name.to_string();
-        if alternates.iter().any(|a| a.name == alt_name) {
+        if part.alternates().iter().any(|a| a.name == alt_name) {
             let entry = self.alternates.entry(part_name).or_default();
             *entry = alt_name;
         }
     }
+
+    /// The alternate applied to `part`, or `None` if it names a missing one.
+    fn applied_alternate<'a>(&self, part: &'a Part) -> Option<Option<&'a Alternate>> {
+        match self.alternates.get(part.name()) {
+            Some(alt_name) => part
+                .alternates()
+                .iter()
+                .find(|a| &a.name == alt_name)
+                .map(Some),
+            None => Some(None),
+        }
+    }
+
+    fn get_position(&self, part: &Part) -> Option<Transform> {
+        let position_steps = match self.applied_alternate(part)? {
+            Some(Alternate {
+                position: Some(position),
+                ..
+            }) => position,
+            _ => part.position(),
+        };
+
+        let position = position_steps
+            .iter()
+            .fold(Transform::new(), |pos, step| pos.step(*step));
+
+        self.pose(position, part)
+    }
+
+    /// Where the anchor called `name` ends up once its part is posed.
+    pub fn anchor(&self, name: &str) -> Option<Transform> {
+        let anchor = self.anchors.iter().find(|a| a.name == name)?;
+        let part = self.parts.iter().find(|p| p.name() == anchor.part)?;
+        let position = anchor
+            .position
+            .iter()
+            .fold(Transform::new(), |pos, step| pos.step(*step));
+
+        self.pose(position, part)
+    }
+
+    /// The dimensions, whether it's mirrored and the posed position of the
+    /// cuboid part called `name`.
+    pub fn cuboid(&self, name: &str) -> Option<(Dimensions3D, bool, Transform)> {
+        let part = self.parts.iter().find(|p| p.name() == name)?;
+        let Part::Cuboid(cuboid) = part else {
+            return None;
+        };
+
+        Some((
+            cuboid.dimensions.into(),
+            cuboid.mirror,
+            self.get_position(part)?,
+        ))
+    }
+
+    /// Applies the pose of `part`, then of each of its ancestors. Ancestors
+    /// that an alternate moves take `part` along, by as far as they moved.
+    fn pose(&self, mut position: Transform, part: &Part) -> Option<Transform> {
+        let mut next = Some(part);
+        for i in 0..self.parts.len() {
+            let Some(part) = next else {
+                break;
+            };
+
+            let alternate = self.applied_alternate(part)?;
+            if let (1.., Some(moved)) = (i, alternate.and_then(|a| a.position.as_ref())) {
+                position = position
+                    .then(&transform(part.position()).inverse())
+                    .then(&transform(moved));
+            }
+            let turns = part
+                .rotate()
+                .iter()
+                .chain(alternate.map(|a| a.rotate.as_slice()).unwrap_or_default())
+                .chain(self.rotations.get(part.name()).into_iter().flatten());
+            position = turns.fold(position, |pos, turn| pos.turn(*turn, part.pivot()));
+
+            next = part
+                .parent()
+                .and_then(|p| self.parts.iter().find(|q| q.name() == p));
+        }
+
+        Some(position)
+    }
+
+    /// The parents of `part`, nearest first, stopping early if a part turns
+    /// out to be its own ancestor.
+    fn ancestors<'a>(&'a self, part: &'a Part) -> Vec<&'a Part> {
+        let mut ancestors = vec![];
+        let mut next = part;
+        for _ in 0..self.parts.len() {
+            let Some(parent) = next
+                .parent()
+                .and_then(|p| self.parts.iter().find(|q| q.name() == p))
+            else {
+                break;
+            };
+
+            ancestors.push(parent);
+            if parent.name() == part.name() {
+                break;
+            }
+            next = parent;
+        }
+
+        ancestors
+    }
+
+    /// Checks for mistakes that would otherwise only show up, or panic, while
+    /// drawing. `texture` is the size of the model's texture and `textures`
+    /// those of its extra textures, where they're known.
+    pub fn validate(
+        &self,
+        texture: Option<(u32, u32)>,
+        textures: &HashMap<String, (u32, u32)>,
+    ) -> Vec<Problem> {
+        let mut report = Report::new(&self.name);
+
+        for (i, part) in self.parts.iter().enumerate() {
+            let name = part.name();
+            if self.parts[..i].iter().any(|p| p.name() == name) {
+                report.error(Some(name), "has the same name as an earlier part");
+            }
+
+            if let Some(parent) = part.parent() {
+                if !self.parts.iter().any(|p| p.name() == parent) {
+                    report.error(Some(name), format!("parent \"{parent}\" doesn't exist"));
+                } else if self.ancestors(part).iter().any(|a| a.name() == name) {
+                    report.error(Some(name), "is its own ancestor");
+                }
+            }
+
+            let size = match part.texture() {
+                Some(texture) => textures.get(texture).copied(),
+                None => texture,
+            };
+            if let Some((width, height)) = size {
+                for (face, (u, v), Dimensions2D { x: w, y: h }) in part.faces() {
+                    if u + w > width || v + h > height {
+                        let message = format!(
+                            "{face} ({u}, {v}) to ({}, {}) is outside the {width}x{height} texture",
+                            u + w,
+                            v + h
+                        );
+                        report.error(Some(name), message);
+                    }
+                }
+            }
+
+            if let Part::Cuboid(cuboid) = part {
+                match (&cuboid.fill, cuboid.fill()) {
+                    (Some(fill), None) => {
+                        report.error(Some(name), format!("unknown fill \"{fill}\""))
+                    }
+                    (_, Some(Fill::Block(block))) if !is_block(&block) => {
+                        report.error(Some(name), format!("fill block \"{block}\" doesn't exist"))
+                    }
+                    _ => {}
+                }
+                for face in cuboid.offsets.all() {
+                    let rotation = face.orientation().rotation;
+                    if rotation % 90 != 0 || rotation >= 360 {
+                        let message = format!("face rotation {rotation} isn't 0, 90, 180 or 270");
+                        report.error(Some(name), message);
+                    }
+                }
+            }
+
+            for (j, alternate) in part.alternates().iter().enumerate() {
+                if part.alternates()[..j]
+                    .iter()
+                    .any(|a| a.name == alternate.name)
+                {
+                    let message = format!("has two alternates called \"{}\"", alternate.name);
+                    report.error(Some(name), message);
+                }
+            }
+        }
+
+        let mut check_alternates = |owner: &str, alternates: &HashMap<String, String>| {
+            for (part_name, alt_name) in alternates.iter() {
+                let message = match self.parts.iter().find(|p| p.name() == part_name) {
+                    None => format!("{owner} names missing part \"{part_name}\""),
+                    Some(part) if !part.alternates().iter().any(|a| &a.name == alt_name) => {
+                        format!("{owner} names missing alternate \"{alt_name}\"")
+                    }
+                    _ => continue,
+                };
+                report.error(Some(part_name), message);
+            }
+        };
+        check_alternates("default", &self.alternates);
+        for pose in self.poses.iter() {
+            check_alternates(&format!("pose \"{}\"", pose.name), &pose.alternates);
+        }
+
+        for pose in self.poses.iter() {
+            for part_name in pose.rotate.keys() {
+                if !self.parts.iter().any(|p| p.name() == part_name) {
+                    let message = format!("pose \"{}\" rotates missing part", pose.name);
+                    report.error(Some(part_name), message);
+                }
+            }
+        }
+
+        for anchor in self.anchors.iter() {
+            if !self.parts.iter().any(|p| p.name() == anchor.part) {
+                let message = format!("anchor \"{}\" is on missing part", anchor.name);
+                report.error(Some(&anchor.part), message);
+            }
+        }
+
+        for layer in self.armor.iter() {
+            match self.parts.iter().find(|p| p.name() == layer.part) {
+                Some(Part::Cuboid(_)) => {}
+                Some(Part::Flat(_)) => report.error(Some(&layer.part), "armor needs a cuboid part"),
+                None => report.error(Some(&layer.part), "armor is on missing part"),
+            }
+        }
+
+        // Parts often overlap their parents on purpose, such as a hat around a
+        // head, so only unrelated parts are compared.
+        let bounds = self
+            .parts
+            .iter()
+            .filter_map(|p| Some((p, self.bounds(p)?)))
+            .collect::<Vec<_>>();
+        for (i, (part, (low, high))) in bounds.iter().enumerate() {
+            for (other, (other_low, other_high)) in bounds[..i].iter() {
+                if self
+                    .ancestors(part)
+                    .iter()
+                    .any(|a| a.name() == other.name())
+                    || self
+                        .ancestors(other)
+                        .iter()
+                        .any(|a| a.name() == part.name())
+                {
+                    continue;
+                }
+
+                let overlaps =
+                    (0..3).all(|a| low[a].max(other_low[a]) < high[a].min(other_high[a]));
+                if overlaps {
+                    let message = format!("overlaps \"{}\"", other.name());
+                    report.warning(Some(part.name()), message);
+                }
+            }
+        }
+
+        report.problems
+    }
+
+    /// The space a part takes up once posed, if it's a cuboid that stays on
+    /// the grid, in the units `Transform::apply_f64` uses.
+    fn bounds(&self, part: &Part) -> Option<([f64; 3], [f64; 3])> {
+        let Part::Cuboid(cuboid) = part else {
+            return None;
+        };
+        let position = self.get_position(part)?;
+        if !position.is_axis_aligned() {
+            return None;
+        }
+
+        // Inflated layers are left at their own size, since they're meant to
+        // wrap around whatever is next to them.
+        let [x, y, z] = cuboid.dimensions.map(|d| d as f64);
+        let corners = (
+            position.apply_f64([1.0, 1.0 - y, 1.0 - z]),
+            position.apply_f64([x + 1.0, 1.0, 1.0]),
+        );
+
+        let low = [0, 1, 2].map(|a| corners.0[a].min(corners.1[a]));
+        let high = [0, 1, 2].map(|a| corners.0[a].max(corners.1[a]));
+        Some((low, high))
+    }
+}
+
+/// The transform made of `steps`, in order.
+fn transform(steps: &[TransformStep]) -> Transform {
+    steps
+        .iter()
+        .fold(Transform::new(), |pos, step| pos.step(*step))
 }
 
 impl Model for JsonModel {
     fn parts(&self) -> Vec<Primitive> {
         let mut drawables = vec![];
         for part in self.parts.iter() {
-            let primitive = match part {
-                Part::Cuboid(cuboid) => cuboid.parts(&self.alternates),
-                Part::Flat(flat) => flat.parts(&self.alternates),
+            let Some(position) = self.get_position(part) else {
+                println!("Skipping invalid alternate for {}.", part.name());
+                continue;
             };
 
-            if let Some(primitive) = primitive {
-                drawables.push(primitive);
-            } else {
-                println!("Skipping invalid alternate for {}.", part.name());
-            }
+            let origin = match part.texture() {
+                Some(texture) => match self.texture_origins.get(texture) {
+                    Some(origin) => *origin,
+                    None => continue,
+                },
+                None => (0, 0),
+            };
+
+            drawables.push(match part {
+                Part::Cuboid(cuboid) => cuboid.parts(position, origin),
+                Part::Flat(flat) => flat.parts(position, origin),
+            });
         }
 
         drawables
@@ -91,6 +525,88 @@ impl Part {
             Part::Flat(flat) => &flat.name,
         }
     }
+
+    fn parent(&self) -> Option<&str> {
+        match self {
+            Part::Cuboid(cuboid) => cuboid.parent.as_deref(),
+            Part::Flat(flat) => flat.parent.as_deref(),
+        }
+    }
+
+    fn pivot(&self) -> [f32; 3] {
+        match self {
+            Part::Cuboid(cuboid) => cuboid.pivot,
+            Part::Flat(flat) => flat.pivot,
+        }
+    }
+
+    fn texture(&self) -> Option<&str> {
+        match self {
+            Part::Cuboid(cuboid) => cuboid.texture.as_deref(),
+            Part::Flat(flat) => flat.texture.as_deref(),
+        }
+    }
+
+    fn rotate(&self) -> &[Turn] {
+        match self {
+            Part::Cuboid(cuboid) => &cuboid.rotate,
+            Part::Flat(flat) => &flat.rotate,
+        }
+    }
+
+    fn position(&self) -> &[TransformStep] {
+        match self {
+            Part::Cuboid(cuboid) => &cuboid.position,
+            Part::Flat(flat) => &flat.position,
+        }
+    }
+
+    fn alternates(&self) -> &[Alternate] {
+        match self {
+            Part::Cuboid(cuboid) => &cuboid.alternates,
+            Part::Flat(flat) => &flat.alternates,
+        }
+    }
+
+    /// Where each face is in the part's texture, and its size.
+    fn faces(&self) -> Vec<(&'static str, (u32, u32), Dimensions2D)> {
+        match self {
+            Part::Cuboid(cuboid) => {
+                let [x, y, z] = cuboid.dimensions;
+                let offsets = cuboid.offsets((0, 0));
+                let orientations = cuboid.orientations();
+                // Turned faces read a region on its side.
+                let size = |orientation: UvOrientation, size| {
+                    let (width, height) = orientation.source_size(size);
+                    [width, height].into()
+                };
+                vec![
+                    ("top face", offsets.top, size(orientations.top, (x, z))),
+                    (
+                        "bottom face",
+                        offsets.bottom,
+                        size(orientations.bottom, (x, z)),
+                    ),
+                    (
+                        "right face",
+                        offsets.right,
+                        size(orientations.right, (z, y)),
+                    ),
+                    ("left face", offsets.left, size(orientations.left, (z, y))),
+                    (
+                        "front face",
+                        offsets.front,
+                        size(orientations.front, (x, y)),
+                    ),
+                    ("back face", offsets.back, size(orientations.back, (x, y))),
+                ]
+            }
+            Part::Flat(flat) => {
+                let [u, v] = flat.offset;
+                vec![("face", (u, v), flat.dimensions.into())]
+            }
+        }
+    }
 }
 
 #[derive(Deserialize)]
@@ -99,45 +615,98 @@ pub struct CuboidPart {
     pub dimensions: [u32; 3],
     pub offsets: Offsets,
     pub position: Vec<TransformStep>,
+    /// Part whose pose this part follows.
+    pub parent: Option<String>,
+    /// Point, in model units, that alternates rotate this part about.
+    #[serde(default)]
+    pub pivot: [f32; 3],
+    /// Rotations about the pivot that children follow, applied before any
+    /// alternate.
+    #[serde(default)]
+    pub rotate: Vec<Turn>,
+    /// Extra texture the offsets are in, instead of the model's own.
+    pub texture: Option<String>,
+    /// How many texels the part grows by on every side, with its faces
+    /// stretched to fit, like the layers the game draws over others.
+    #[serde(default)]
+    pub inflate: u32,
+    /// Mirrors the texture left to right, so a part can reuse the texture of
+    /// its opposite, like a left arm drawn from the right arm's texture.
+    #[serde(default)]
+    pub mirror: bool,
+    /// What fills the part, instead of the statue's fill, like `--fill`.
+    pub fill: Option<String>,
+    /// Where parts overlap, the one with the highest priority is drawn.
+    #[serde(default)]
+    pub priority: i32,
     #[serde(default)]
     pub alternates: Vec<Alternate>,
+    /// Faces stretched by `JsonModel::inflate_parts`.
+    #[serde(skip)]
+    stretched: Option<TextureOffsets>,
 }
 
 impl CuboidPart {
-    fn parts(&self, applied_alternates: &HashMap<String, String>) -> Option<Primitive> {
-        let position = self.get_position(applied_alternates)?;
+    /// The part's own fill, leaving any `validate` rejects to the statue's.
+    fn fill(&self) -> Option<Fill> {
+        self.fill.as_deref().and_then(Fill::from_name)
+    }
+
+    fn parts(&self, position: Transform, origin: (u32, u32)) -> Primitive {
+        if let Some(stretched) = self.stretched {
+            let grow = self.inflate as i32;
+            return Primitive::Cuboid(Cuboid {
+                dimensions: self.dimensions.map(|d| d + 2 * self.inflate).into(),
+                offsets: stretched,
+                orientations: self.orientations(),
+                mirror: self.mirror,
+                fill: self.fill(),
+                priority: Priority {
+                    order: self.priority,
+                    overlay: true,
+                },
+                position: Transform::new().translate(-grow, grow, grow).then(&position),
+            });
+        }
 
-        Some(Primitive::Cuboid(Cuboid {
+        Primitive::Cuboid(Cuboid {
             dimensions: self.dimensions.into(),
-            offsets: TextureOffsets {
-                top: self.offsets.top.into(),
-                bottom: self.offsets.bottom.into(),
-                right: self.offsets.right.into(),
-                left: self.offsets.left.into(),
-                front: self.offsets.front.into(),
-                back: self.offsets.back.into(),
+            offsets: self.offsets(origin),
+            orientations: self.orientations(),
+            mirror: self.mirror,
+            fill: self.fill(),
+            priority: Priority {
+                order: self.priority,
+                overlay: false,
             },
             position,
-        }))
+        })
     }
 
-    fn get_position(&self, applied_alternates: &HashMap<String, String>) -> Option<Transform> {
-        let position_steps = match applied_alternates.get(&self.name) {
-            Some(alt_name) => {
-                &self
-                    .alternates
-                    .iter()
-                    .find(|a| &a.name == alt_name)?
-                    .position
-            }
-            None => &self.position,
+    fn offsets(&self, (u, v): (u32, u32)) -> TextureOffsets {
+        let offset = |face: &FaceOffset| {
+            let [x, y] = face.uv();
+            (u + x, v + y)
         };
+        TextureOffsets {
+            top: offset(&self.offsets.top),
+            bottom: offset(&self.offsets.bottom),
+            right: offset(&self.offsets.right),
+            left: offset(&self.offsets.left),
+            front: offset(&self.offsets.front),
+            back: offset(&self.offsets.back),
+        }
+    }
 
-        let position = position_steps
-            .iter()
-            .fold(Transform::new(), |pos, step| pos.step(*step));
-
-        Some(position)
+    fn orientations(&self) -> FaceOrientations {
+        FaceOrientations {
+            top: self.offsets.top.orientation(),
+            bottom: self.offsets.bottom.orientation(),
+            right: self.offsets.right.orientation(),
+            left: self.offsets.left.orientation(),
+            front: self.offsets.front.orientation(),
+            back: self.offsets.back.orientation(),
+        }
     }
 }
 
@@ -147,53 +716,196 @@ pub struct FlatPart {
     pub dimensions: [u32; 2],
     pub offset: [u32; 2],
     pub position: Vec<TransformStep>,
+    /// Part whose pose this part follows.
+    pub parent: Option<String>,
+    /// Point, in model units, that alternates rotate this part about.
+    #[serde(default)]
+    pub pivot: [f32; 3],
+    /// Rotations about the pivot that children follow, applied before any
+    /// alternate.
+    #[serde(default)]
+    pub rotate: Vec<Turn>,
+    /// Extra texture the offsets are in, instead of the model's own.
+    pub texture: Option<String>,
+    /// Where parts overlap, the one with the highest priority is drawn.
+    #[serde(default)]
+    pub priority: i32,
     #[serde(default)]
     pub alternates: Vec<Alternate>,
 }
 
 impl FlatPart {
-    fn parts(&self, applied_alternates: &HashMap<String, String>) -> Option<Primitive> {
-        let position = self.get_position(applied_alternates)?;
-
-        Some(Primitive::Plane(Plane {
+    fn parts(&self, position: Transform, (u, v): (u32, u32)) -> Primitive {
+        Primitive::Plane(Plane {
             dimensions: self.dimensions.into(),
-            offset: self.offset.into(),
+            offset: (u + self.offset[0], v + self.offset[1]),
+            priority: Priority {
+                order: self.priority,
+                overlay: false,
+            },
             position,
-        }))
+        })
     }
+}
 
-    fn get_position(&self, applied_alternates: &HashMap<String, String>) -> Option<Transform> {
-        let position_steps = match applied_alternates.get(&self.name) {
-            Some(alt_name) => {
-                &self
-                    .alternates
-                    .iter()
-                    .find(|a| &a.name == alt_name)?
-                    .position
-            }
-            None => &self.position,
-        };
-
-        let position = position_steps
-            .iter()
-            .fold(Transform::new(), |pos, step| pos.step(*step));
+#[derive(Deserialize)]
+pub struct Offsets {
+    top: FaceOffset,
+    bottom: FaceOffset,
+    right: FaceOffset,
+    left: FaceOffset,
+    front: FaceOffset,
+    back: FaceOffset,
+}
 
-        Some(position)
+impl Offsets {
+    fn all(&self) -> [&FaceOffset; 6] {
+        [
+            &self.top,
+            &self.bottom,
+            &self.right,
+            &self.left,
+            &self.front,
+            &self.back,
+        ]
     }
 }
 
+/// Where a face is in the texture, either as just its top left corner or
+/// along with how its texture is turned.
 #[derive(Deserialize)]
-pub struct Offsets {
-    top: [u32; 2],
-    bottom: [u32; 2],
-    right: [u32; 2],
-    left: [u32; 2],
-    front: [u32; 2],
-    back: [u32; 2],
+#[serde(untagged)]
+pub enum FaceOffset {
+    Origin([u32; 2]),
+    Oriented {
+        uv: [u32; 2],
+        /// Degrees clockwise, in quarter turns.
+        #[serde(default)]
+        rotation: u32,
+        /// Mirrors the face left to right.
+        #[serde(default)]
+        mirror: bool,
+        /// Flips the face top to bottom.
+        #[serde(default)]
+        flip: bool,
+    },
+}
+
+impl FaceOffset {
+    fn uv(&self) -> [u32; 2] {
+        match self {
+            FaceOffset::Origin(uv) => *uv,
+            FaceOffset::Oriented { uv, .. } => *uv,
+        }
+    }
+
+    fn orientation(&self) -> UvOrientation {
+        match self {
+            FaceOffset::Origin(_) => UvOrientation::default(),
+            FaceOffset::Oriented {
+                rotation,
+                mirror,
+                flip,
+                ..
+            } => UvOrientation {
+                rotation: *rotation,
+                mirror: *mirror,
+                flip: *flip,
+            },
+        }
+    }
 }
 
+/// An alternate either replaces a part's position outright or rotates it
+/// about its pivot, carrying its children along.
 #[derive(Deserialize)]
 pub struct Alternate {
     name: String,
+    position: Option<Vec<TransformStep>>,
+    #[serde(default)]
+    rotate: Vec<Turn>,
+}
+
+/// A named set of alternates and rotations, so a whole model can be posed at
+/// once.
+#[derive(Deserialize)]
+pub struct Pose {
+    name: String,
+    #[serde(default)]
+    alternates: HashMap<String, String>,
+    #[serde(default)]
+    rotate: HashMap<String, Vec<Turn>>,
+}
+
+/// A different look for a model that keeps its parts, like a sheep's wool color
+/// or a villager's profession.
+#[derive(Deserialize)]
+pub struct Variant {
+    name: String,
+    /// Replaces the model's texture.
+    texture: Option<String>,
+    /// Replaces or adds to the model's extra textures.
+    #[serde(default)]
+    textures: HashMap<String, String>,
+    /// Textures with the same layout as the model's, drawn over it.
+    #[serde(default)]
+    overlays: Vec<String>,
+    /// Colors to multiply extra textures by, such as for dyed wool.
+    #[serde(default)]
+    tint: HashMap<String, [u8; 3]>,
+}
+
+/// A point that follows a part as it's posed, such as a hand holding an item.
+#[derive(Deserialize)]
+pub struct Anchor {
+    name: String,
+    part: String,
     position: Vec<TransformStep>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::File;
+
+    #[test]
+    fn shipped_models_fit_their_textures() {
+        let file = File::open("models.json").expect("can't open models.json");
+        let models: Vec<JsonModel> = serde_json::from_reader(file).expect("invalid models.json");
+
+        for model in models.iter() {
+            let texture = model.texture_size;
+            assert!(texture.is_some(), "{}: no texture size", model.name);
+
+            let errors = model
+                .validate(texture, &model.texture_sizes)
+                .into_iter()
+                .filter(|p| p.is_error())
+                .map(|p| p.to_string())
+                .collect::<Vec<_>>();
+            assert!(errors.is_empty(), "{}", errors.join("\n"));
+
+            for part in model.parts.iter() {
+                let size = match part.texture() {
+                    Some(name) => model.texture_sizes.get(name).copied(),
+                    None => texture,
+                };
+                let Some((width, height)) = size else {
+                    panic!(
+                        "{}: no size for texture of part \"{}\"",
+                        model.name,
+                        part.name()
+                    );
+                };
+                for (face, (u, v), Dimensions2D { x: w, y: h }) in part.faces() {
+                    assert!(
+                        u + w <= width && v + h <= height,
+                        "{}: {face} of part \"{}\" is outside its texture",
+                        model.name,
+                        part.name()
+                    );
+                }
+            }
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 #[allow(dead_code)]
 #[derive(Clone, PartialEq)]
@@ -22,7 +22,7 @@ impl Tag {
     pub fn serialize(&self, name: &str, data: &mut Vec<u8>) {
         data.push(self.id());
 
-        let name_len = name.bytes().len() as u16;
+        let name_len = name.len() as u16;
         data.extend_from_slice(&name_len.to_be_bytes());
         data.extend(name.bytes());
 
@@ -58,12 +58,13 @@ impl Tag {
                 }
             }
             Tag::String(v) => {
-                let len = v.bytes().len() as u16;
+                let len = v.len() as u16;
                 data.extend_from_slice(&len.to_be_bytes());
                 data.extend(v.bytes());
             }
             Tag::List(v) => {
-                data.push(v[0].id()); // Assumed to be homogenous
+                // Assumed to be homogenous, and empty lists hold nothing.
+                data.push(v.first().map_or(Tag::End.id(), Tag::id));
                 let len = v.len() as i32;
                 data.extend_from_slice(&len.to_be_bytes());
                 for t in v.iter() {
@@ -117,6 +118,8 @@ pub struct Structure {
     pub size: Coords,
     pub palette: Vec<Palette>,
     pub blocks: Vec<Block>,
+    /// Where each position's block is in `blocks`.
+    index: HashMap<(i32, i32, i32), usize>,
 }
 
 impl Structure {
@@ -126,6 +129,7 @@ impl Structure {
             size: Coords::new(0, 0, 0),
             palette: vec![],
             blocks: vec![],
+            index: HashMap::new(),
         }
     }
 
@@ -150,6 +154,31 @@ impl Structure {
     }
 
     pub fn set_block(&mut self, pos: Coords, block_id: Palette) {
+        self.place(pos, block_id, None);
+    }
+
+    /// Sets a block, unless one with a higher `rank` is already there. Blocks
+    /// set any other way rank below all others.
+    pub fn set_ranked(&mut self, pos: impl Into<Coords>, block_id: Palette, rank: i64) {
+        let pos = pos.into();
+        if let Some(&i) = self.index.get(&(pos.x, pos.y, pos.z)) {
+            if self.blocks[i].rank > rank {
+                return;
+            }
+        }
+        self.place(pos, block_id, Some(rank));
+    }
+
+    /// Sets a block that has a block entity, like a sign with its text.
+    pub fn set_entity(&mut self, pos: impl Into<Coords>, block_id: Palette, nbt: Tag) {
+        let pos = pos.into();
+        let key = (pos.x, pos.y, pos.z);
+        self.place(pos, block_id, None);
+        let i = self.index[&key];
+        self.blocks[i].nbt = Some(nbt);
+    }
+
+    fn place(&mut self, pos: Coords, block_id: Palette, rank: Option<i64>) {
         let state = match self.palette.iter().position(|p| p == &block_id) {
             Some(index) => index as i32,
             None => {
@@ -158,13 +187,74 @@ impl Structure {
             }
         };
 
-        if let Some(block) = self.blocks.iter_mut().find(|b| b.pos == pos) {
-            block.state = state;
-        } else {
-            self.blocks.push(Block { state, pos });
+        match self.index.get(&(pos.x, pos.y, pos.z)) {
+            Some(&i) => {
+                let block = &mut self.blocks[i];
+                block.state = state;
+                block.rank = rank.unwrap_or(block.rank);
+                block.nbt = None;
+            }
+            None => {
+                self.index.insert((pos.x, pos.y, pos.z), self.blocks.len());
+                let rank = rank.unwrap_or(i64::MIN);
+                self.blocks.push(Block {
+                    state,
+                    pos,
+                    rank,
+                    nbt: None,
+                });
+            }
         }
     }
 
+    /// The positions of blocks with a block on every side, not counting
+    /// those that are `see_through`.
+    pub fn hidden(&self, see_through: impl Fn(&Palette) -> bool) -> HashSet<(i32, i32, i32)> {
+        let opaque = self
+            .palette
+            .iter()
+            .map(|p| !see_through(p))
+            .collect::<Vec<_>>();
+        let covered = |pos| {
+            self.index
+                .get(&pos)
+                .is_some_and(|&i| opaque[self.blocks[i].state as usize])
+        };
+
+        self.blocks
+            .iter()
+            .map(|b| (b.pos.x, b.pos.y, b.pos.z))
+            .filter(|&(x, y, z)| {
+                [
+                    (x - 1, y, z),
+                    (x + 1, y, z),
+                    (x, y - 1, z),
+                    (x, y + 1, z),
+                    (x, y, z - 1),
+                    (x, y, z + 1),
+                ]
+                .into_iter()
+                .all(covered)
+            })
+            .collect()
+    }
+
+    pub fn remove(&mut self, positions: &HashSet<(i32, i32, i32)>) {
+        self.blocks
+            .retain(|b| !positions.contains(&(b.pos.x, b.pos.y, b.pos.z)));
+        self.index = self
+            .blocks
+            .iter()
+            .enumerate()
+            .map(|(i, b)| ((b.pos.x, b.pos.y, b.pos.z), i))
+            .collect();
+    }
+
+    pub fn is_set(&self, pos: impl Into<Coords>) -> bool {
+        let pos = pos.into();
+        self.index.contains_key(&(pos.x, pos.y, pos.z))
+    }
+
     pub fn normalize(&mut self) {
         if self.blocks.is_empty() {
             return;
@@ -190,10 +280,13 @@ impl Structure {
             z: max_z - min_z + 1,
         };
 
-        for block in self.blocks.iter_mut() {
+        self.index.clear();
+        for (i, block) in self.blocks.iter_mut().enumerate() {
             block.pos.x += -min_x;
             block.pos.y += -min_y;
             block.pos.z += -min_z;
+            self.index
+                .insert((block.pos.x, block.pos.y, block.pos.z), i);
         }
     }
 
@@ -222,6 +315,12 @@ impl Coords {
     }
 }
 
+impl From<[i32; 3]> for Coords {
+    fn from([x, y, z]: [i32; 3]) -> Self {
+        Self { x, y, z }
+    }
+}
+
 impl From<(i32, i32, i32)> for Coords {
     fn from(value: (i32, i32, i32)) -> Self {
         Self {
@@ -264,14 +363,22 @@ impl Palette {
 pub struct Block {
     pub state: i32,
     pub pos: Coords,
+    /// Which block wins when another is set in the same place, see
+    /// `Structure::set_ranked`.
+    pub rank: i64,
+    /// The block entity, for blocks that have one.
+    pub nbt: Option<Tag>,
 }
 
 impl Block {
     pub fn to_nbt(&self) -> Tag {
-        let map = HashMap::from([
+        let mut map = HashMap::from([
             ("state".into(), Tag::Int(self.state)),
             ("pos".into(), self.pos.to_nbt()),
         ]);
+        if let Some(nbt) = &self.nbt {
+            map.insert("nbt".into(), nbt.clone());
+        }
         Tag::Compound(map)
     }
 }
@@ -0,0 +1,106 @@
+use crate::nbt::{Palette, Structure, Tag};
+use std::collections::HashMap;
+
+/// The outline of a pedestal seen from above.
+#[derive(Copy, Clone)]
+pub enum Shape {
+    Square,
+    /// A circle, or an ellipse under statues longer one way than the other.
+    Round,
+    /// A square that grows by a block on every side with each layer down.
+    Stepped,
+}
+
+impl Shape {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "square" => Some(Shape::Square),
+            "round" => Some(Shape::Round),
+            "stepped" => Some(Shape::Stepped),
+            _ => None,
+        }
+    }
+}
+
+/// A base built under a statue, reaching a block past it on every side.
+pub struct Pedestal {
+    pub shape: Shape,
+    /// The block it's built from, by its ID.
+    pub block: String,
+    /// How many layers of blocks it has.
+    pub height: i32,
+    /// Text for a sign on its front, which faces the same way as the statue.
+    pub name_plate: Option<String>,
+}
+
+impl Pedestal {
+    /// Builds the pedestal under everything already in `structure`.
+    pub fn build(&self, structure: &mut Structure) {
+        let Some((low, high)) = bounds(structure) else {
+            return;
+        };
+
+        let center = [0, 2].map(|i| (low[i] + high[i]) as f64 / 2.0);
+        let radius = [0, 2].map(|i| (high[i] - low[i]) as f64 / 2.0 + 1.5);
+        for layer in 0..self.height {
+            let y = low[1] - 1 - layer;
+            let margin = match self.shape {
+                Shape::Stepped => 1 + layer,
+                _ => 1,
+            };
+            for x in low[0] - margin..=high[0] + margin {
+                for z in low[2] - margin..=high[2] + margin {
+                    let dx = (x as f64 - center[0]) / radius[0];
+                    let dz = (z as f64 - center[1]) / radius[1];
+                    if matches!(self.shape, Shape::Round) && dx * dx + dz * dz > 1.0 {
+                        continue;
+                    }
+                    structure.set((x, y, z), Palette::new(&self.block));
+                }
+            }
+        }
+
+        // The sign hangs off the top layer, in the middle of its front.
+        if let Some(name) = &self.name_plate {
+            let (x, y) = ((low[0] + high[0]).div_euclid(2), low[1] - 1);
+            let front = (low[2] - 1..=high[2] + 1)
+                .rev()
+                .find(|&z| structure.is_set((x, y, z)))
+                .unwrap_or(high[2]);
+            let sign = Palette::new("oak_wall_sign").with_property("facing", "south");
+            structure.set_entity((x, y, front + 1), sign, sign_text(name));
+        }
+    }
+}
+
+/// The corners of the box around every block in `structure`.
+fn bounds(structure: &Structure) -> Option<([i32; 3], [i32; 3])> {
+    let mut blocks = structure.blocks.iter().map(|b| [b.pos.x, b.pos.y, b.pos.z]);
+    let first = blocks.next()?;
+    Some(blocks.fold((first, first), |(low, high), pos| {
+        (
+            [0, 1, 2].map(|i| low[i].min(pos[i])),
+            [0, 1, 2].map(|i| high[i].max(pos[i])),
+        )
+    }))
+}
+
+/// The block entity for a waxed sign with `text` on the second line of its
+/// front, so it can't be edited by accident.
+fn sign_text(text: &str) -> Tag {
+    let line = |text: &str| Tag::String(serde_json::json!({ "text": text }).to_string());
+    let side = |lines: [&str; 4]| {
+        Tag::Compound(HashMap::from([
+            ("messages".into(), Tag::List(lines.map(line).to_vec())),
+            ("color".into(), Tag::String("black".into())),
+            ("has_glowing_text".into(), Tag::Byte(0)),
+        ]))
+    };
+
+    Tag::Compound(HashMap::from([
+        ("id".into(), Tag::String("minecraft:sign".into())),
+        ("front_text".into(), side(["", text, "", ""])),
+        ("back_text".into(), side(["", "", "", ""])),
+        ("is_waxed".into(), Tag::Byte(1)),
+    ]))
+}
@@ -0,0 +1,99 @@
+use crate::nbt::{Palette, Structure, Tag};
+use crate::Error;
+use serde::Deserialize;
+use std::fs::File;
+
+/// Several statues built together into one structure.
+#[derive(Deserialize)]
+pub struct Scene {
+    pub statues: Vec<Placement>,
+}
+
+impl Scene {
+    pub fn load(path: &str) -> Result<Self, Error> {
+        let file = File::open(path)?;
+        Ok(serde_json::from_reader(file)?)
+    }
+}
+
+/// One statue in a scene, and where it goes.
+#[derive(Deserialize)]
+pub struct Placement {
+    /// The same arguments as for building the statue alone, such as
+    /// `["player", "steve", "--pose=sitting"]`. They can say anything about
+    /// the statue and its own pedestal, but `--cull` and `--supports` work on
+    /// the finished structure, so they go after the scene's path instead.
+    pub args: Vec<String>,
+    /// Where the statue's origin goes, in blocks.
+    #[serde(default)]
+    pub offset: [i32; 3],
+    /// Degrees clockwise seen from above, in quarter turns.
+    #[serde(default)]
+    pub rotation: i32,
+}
+
+impl Placement {
+    /// How many quarter turns clockwise the statue is turned, if its rotation
+    /// is a whole number of them.
+    pub fn turns(&self) -> Option<i32> {
+        (self.rotation % 90 == 0).then(|| (self.rotation / 90).rem_euclid(4))
+    }
+
+    /// Copies a statue into the scene, keeping blocks already there, and
+    /// returns how many of its blocks overlapped them.
+    pub fn place(&self, scene: &mut Structure, statue: &Structure) -> usize {
+        let turns = self.turns().unwrap_or(0);
+        let mut overlaps = 0;
+        for block in statue.blocks.iter() {
+            let (mut x, y, mut z) = (block.pos.x, block.pos.y, block.pos.z);
+            for _ in 0..turns {
+                (x, z) = (-z, x);
+            }
+            let [dx, dy, dz] = self.offset;
+            let pos = (x + dx, y + dy, z + dz);
+            if scene.is_set(pos) {
+                overlaps += 1;
+                continue;
+            }
+
+            let palette = rotate(&statue.palette[block.state as usize], turns);
+            match &block.nbt {
+                Some(nbt) => scene.set_entity(pos, palette, nbt.clone()),
+                None => scene.set(pos, palette),
+            }
+        }
+        overlaps
+    }
+}
+
+/// Turns the properties of a block that depend on which way it faces.
+fn rotate(palette: &Palette, turns: i32) -> Palette {
+    const FACINGS: [&str; 4] = ["north", "east", "south", "west"];
+
+    let mut palette = palette.clone();
+    for (name, value) in palette.properties.iter_mut() {
+        let Tag::String(text) = value else {
+            continue;
+        };
+        let turned = match name.as_str() {
+            "facing" => FACINGS
+                .iter()
+                .position(|f| f == text)
+                .map(|i| FACINGS[(i + turns as usize) % 4].to_string()),
+            "axis" if turns % 2 == 1 => match text.as_str() {
+                "x" => Some("z".to_string()),
+                "z" => Some("x".to_string()),
+                _ => None,
+            },
+            "rotation" => text
+                .parse::<i32>()
+                .ok()
+                .map(|r| ((r + 4 * turns) % 16).to_string()),
+            _ => None,
+        };
+        if let Some(turned) = turned {
+            *text = turned;
+        }
+    }
+    palette
+}
@@ -0,0 +1,159 @@
+use crate::nbt::{Palette, Structure};
+
+/// How blocks with nothing under them are held up while the statue is built.
+pub enum Supports {
+    /// Writes columns of scaffolding under those outside the statue to a
+    /// separate structure, to place first and break once they're built.
+    Scaffold,
+    /// Fills columns under those inside the statue with a block by its ID,
+    /// where they stay hidden.
+    Internal(String),
+}
+
+impl Supports {
+    /// Parses `scaffold` or `internal:<block>`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.split_once(':') {
+            Some(("internal", block)) if !block.is_empty() => {
+                Some(Supports::Internal(block.to_string()))
+            }
+            Some(_) => None,
+            None => (name == "scaffold").then_some(Supports::Scaffold),
+        }
+    }
+}
+
+/// The air under blocks with nothing under them, down to the next block or
+/// the bottom of the structure, split by whether the statue encloses it.
+struct Columns {
+    inside: Vec<Vec<[i32; 3]>>,
+    outside: Vec<Vec<[i32; 3]>>,
+}
+
+impl Columns {
+    /// Finds the columns of a normalized structure.
+    fn find(structure: &Structure) -> Self {
+        let outside = Outside::find(structure);
+        let mut columns = Columns {
+            inside: vec![],
+            outside: vec![],
+        };
+        for block in structure.blocks.iter() {
+            let [x, y, z] = [block.pos.x, block.pos.y, block.pos.z];
+            let column = (0..y)
+                .rev()
+                .take_while(|&y| !structure.is_set((x, y, z)))
+                .map(|y| [x, y, z])
+                .collect::<Vec<_>>();
+
+            // Air is only ever outside or enclosed all the way down, since
+            // the column joins it all up.
+            match column.first() {
+                Some(&top) if outside.contains(top) => columns.outside.push(column),
+                Some(_) => columns.inside.push(column),
+                None => {}
+            }
+        }
+        columns
+    }
+}
+
+/// The air around a normalized structure that can be reached from beyond it.
+struct Outside {
+    size: [i32; 3],
+    cells: Vec<bool>,
+}
+
+impl Outside {
+    fn find(structure: &Structure) -> Self {
+        let size = [structure.size.x, structure.size.y, structure.size.z];
+        let mut outside = Outside {
+            size,
+            cells: vec![false; (size[0] * size[1] * size[2]) as usize],
+        };
+
+        // Every air cell on the edge of the box can be reached, and so can any
+        // next to one that can.
+        let mut stack = vec![];
+        for x in 0..size[0] {
+            for y in 0..size[1] {
+                for z in 0..size[2] {
+                    let edge = [x, y, z]
+                        .iter()
+                        .zip(size)
+                        .any(|(&c, s)| c == 0 || c == s - 1);
+                    if edge {
+                        stack.push([x, y, z]);
+                    }
+                }
+            }
+        }
+        while let Some(pos) = stack.pop() {
+            let Some(i) = outside.index(pos) else {
+                continue;
+            };
+            if outside.cells[i] || structure.is_set(pos) {
+                continue;
+            }
+            outside.cells[i] = true;
+
+            let [x, y, z] = pos;
+            stack.extend([
+                [x - 1, y, z],
+                [x + 1, y, z],
+                [x, y - 1, z],
+                [x, y + 1, z],
+                [x, y, z - 1],
+                [x, y, z + 1],
+            ]);
+        }
+        outside
+    }
+
+    fn index(&self, [x, y, z]: [i32; 3]) -> Option<usize> {
+        let [sx, sy, sz] = self.size;
+        let inside = (0..sx).contains(&x) && (0..sy).contains(&y) && (0..sz).contains(&z);
+        inside.then(|| ((x * sy + y) * sz + z) as usize)
+    }
+
+    fn contains(&self, pos: [i32; 3]) -> bool {
+        self.index(pos).is_some_and(|i| self.cells[i])
+    }
+}
+
+/// Props up blocks inside a normalized structure with columns of `block`, and
+/// prints what it did.
+pub fn support(structure: &mut Structure, block: &str) {
+    let columns = Columns::find(structure);
+    let count: usize = columns.inside.iter().map(Vec::len).sum();
+    for pos in columns.inside.iter().flatten() {
+        structure.set(*pos, Palette::new(block));
+    }
+    println!(
+        "Supported {} overhanging blocks with {count} {block}, {} more are outside",
+        columns.inside.len(),
+        columns.outside.len()
+    );
+}
+
+/// Scaffolding under blocks outside a normalized structure, lined up with it,
+/// and prints how much there is, or `None` if nothing there overhangs.
+pub fn scaffolding(structure: &Structure) -> Option<Structure> {
+    let columns = Columns::find(structure);
+    if columns.outside.is_empty() {
+        println!("Nothing outside the statue overhangs, so there's no scaffolding");
+        return None;
+    }
+
+    let mut scaffolding = Structure::new(structure.data_version);
+    for pos in columns.outside.iter().flatten() {
+        scaffolding.set(*pos, Palette::new("scaffolding"));
+    }
+    scaffolding.size = (structure.size.x, structure.size.y, structure.size.z).into();
+    println!(
+        "Scaffolded {} overhanging blocks with {} scaffolding",
+        columns.outside.len(),
+        scaffolding.blocks.len()
+    );
+    Some(scaffolding)
+}
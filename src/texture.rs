@@ -0,0 +1,182 @@
+use crate::drawing::UvOrientation;
+use crate::materials::{find_closest, find_emissive, find_translucent, Direction};
+use crate::nbt::Palette;
+use image::{Rgba, RgbaImage};
+
+/// How the texels that land on one block are combined, when a texture has
+/// more of them than the model has blocks.
+#[derive(Copy, Clone)]
+pub enum Filter {
+    /// Uses the texel under the middle of the block.
+    Nearest,
+    /// Averages the texels, weighting each color by how opaque it is.
+    Average,
+    /// Uses the most common texel.
+    Mode,
+}
+
+impl Filter {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "nearest" => Some(Filter::Nearest),
+            "average" => Some(Filter::Average),
+            "mode" => Some(Filter::Mode),
+            _ => None,
+        }
+    }
+}
+
+/// A texture with `scale` texels along each model unit, such as 2 for a
+/// 128x128 skin. Texture offsets and sizes are always in model units.
+pub struct Texture {
+    pub image: RgbaImage,
+    pub scale: u32,
+    pub filter: Filter,
+    /// Texels less opaque than this are left out.
+    pub alpha_threshold: u8,
+    /// Draws texels that are drawn but not fully opaque with blocks that
+    /// can be seen through, like stained glass.
+    pub glass: bool,
+    /// Marks the texels that glow, by those at least `alpha_threshold`
+    /// opaque in the same place of an image laid out like `image`.
+    pub emissive: Option<RgbaImage>,
+}
+
+/// The part of a texture that covers one face, in model units.
+pub struct Region {
+    pub origin: (u32, u32),
+    pub size: (u32, u32),
+    pub orientation: UvOrientation,
+}
+
+impl Texture {
+    /// The texel at `(x, y)` of `region`, counted in texels rather than units.
+    pub fn texel(&self, region: &Region, (x, y): (u32, u32)) -> Rgba<u8> {
+        let (u, v) = self.position(region, (x, y));
+        *self.image.get_pixel(u, v)
+    }
+
+    /// Whether the texel under `point`, in units from the top left corner of
+    /// `region`, glows.
+    pub fn glows(&self, region: &Region, point: [f64; 2]) -> bool {
+        let Some(mask) = &self.emissive else {
+            return false;
+        };
+        let Some(texel) = self.nearest(region, point) else {
+            return false;
+        };
+        let (u, v) = self.position(region, texel);
+        mask.get_pixel_checked(u, v)
+            .is_some_and(|pixel| pixel.0[3] >= self.alpha_threshold)
+    }
+
+    /// Where the texel at `(x, y)` of `region` is in the image.
+    fn position(&self, region: &Region, (x, y): (u32, u32)) -> (u32, u32) {
+        let size = (region.size.0 * self.scale, region.size.1 * self.scale);
+        let (u, v) = region.orientation.texel((x, y), size);
+        (
+            region.origin.0 * self.scale + u,
+            region.origin.1 * self.scale + v,
+        )
+    }
+
+    /// The texel of `region` under `point`, which is in units, unless the
+    /// region has no texels.
+    fn nearest(&self, region: &Region, point: [f64; 2]) -> Option<(u32, u32)> {
+        let scale = self.scale as f64;
+        let size = [region.size.0 * self.scale, region.size.1 * self.scale];
+        if size.contains(&0) {
+            return None;
+        }
+        let [x, y] = [0, 1].map(|i| ((point[i] * scale).floor().max(0.0) as u32).min(size[i] - 1));
+        Some((x, y))
+    }
+
+    /// The block for a texel on a face towards `normal`, if it's opaque
+    /// enough to draw. Texels that glow only get blocks that give off light.
+    pub fn block(&self, pixel: Rgba<u8>, normal: Direction, glows: bool) -> Option<Palette> {
+        match pixel.0[3] {
+            alpha if alpha < self.alpha_threshold => None,
+            _ if glows => Some(find_emissive(&pixel.0, normal)),
+            alpha if alpha < 255 && self.glass => Some(find_translucent(&pixel.0)),
+            _ => Some(find_closest(&pixel.0, normal)),
+        }
+    }
+
+    /// The color of a block reaching `half` a width and height either side of
+    /// `center`, all in units from the top left corner of `region`.
+    pub fn sample(&self, region: &Region, center: [f64; 2], half: [f64; 2]) -> Rgba<u8> {
+        let scale = self.scale as f64;
+        let size = [region.size.0 * self.scale, region.size.1 * self.scale];
+        if size.contains(&0) {
+            return Rgba([0, 0, 0, 0]);
+        }
+
+        // Blocks no bigger than a texel only need the one under their middle,
+        // which keeps enlarged textures from blurring where texels meet.
+        let small = half.iter().all(|h| 2.0 * h * scale <= 1.0);
+        if small || matches!(self.filter, Filter::Nearest) {
+            let texel = self.nearest(region, center);
+            return texel.map_or(Rgba([0, 0, 0, 0]), |texel| self.texel(region, texel));
+        }
+
+        // A little slack keeps blocks that line up with texels from picking
+        // up their neighbours through rounding.
+        let [xs, ys] = [0, 1].map(|i| {
+            let low = ((center[i] - half[i]) * scale + 1e-6).floor().max(0.0) as u32;
+            let high = ((center[i] + half[i]) * scale - 1e-6).ceil() as u32;
+            let low = low.min(size[i] - 1);
+            low..high.clamp(low + 1, size[i])
+        });
+        let texels = xs.flat_map(|x| ys.clone().map(move |y| (x, y)));
+        let texels = texels.map(|p| self.texel(region, p)).collect::<Vec<_>>();
+
+        match self.filter {
+            Filter::Mode => mode(&texels, self.alpha_threshold),
+            _ => average(&texels),
+        }
+    }
+}
+
+fn average(texels: &[Rgba<u8>]) -> Rgba<u8> {
+    let mut sums = [0u32; 4];
+    for texel in texels.iter() {
+        let alpha = texel.0[3] as u32;
+        for (sum, channel) in sums.iter_mut().zip(texel.0.iter().take(3)) {
+            *sum += *channel as u32 * alpha;
+        }
+        sums[3] += alpha;
+    }
+
+    if sums[3] == 0 {
+        return Rgba([0, 0, 0, 0]);
+    }
+    let [r, g, b] = [0, 1, 2].map(|i| (sums[i] / sums[3]) as u8);
+    Rgba([r, g, b, (sums[3] / texels.len() as u32) as u8])
+}
+
+/// The most common texel, counting every texel less opaque than `threshold`
+/// as the same, and the first found on ties.
+fn mode(texels: &[Rgba<u8>], threshold: u8) -> Rgba<u8> {
+    let mut counts: Vec<(Rgba<u8>, usize)> = vec![];
+    for texel in texels.iter() {
+        let texel = if texel.0[3] < threshold {
+            Rgba([0, 0, 0, 0])
+        } else {
+            *texel
+        };
+        match counts.iter_mut().find(|(t, _)| *t == texel) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((texel, 1)),
+        }
+    }
+
+    let mut best = (Rgba([0, 0, 0, 0]), 0);
+    for (texel, count) in counts.into_iter() {
+        if count > best.1 {
+            best = (texel, count);
+        }
+    }
+
+    best.0
+}
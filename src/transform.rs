@@ -1,24 +1,22 @@
 use crate::drawing::Brush;
 use serde::Deserialize;
+use std::ops::Range;
 
 #[derive(Clone)]
 pub struct Transform {
     steps: Vec<TransformStep>,
-    pub scaling: i32,
+    pub scale: Scale,
 }
 
 impl Transform {
     pub fn new() -> Self {
-        Self {
-            steps: vec![],
-            scaling: 1,
-        }
+        Self::with_scale(Scale::uniform(1.0))
     }
 
-    pub fn with_scale(scaling: u32) -> Self {
+    pub fn with_scale(scale: Scale) -> Self {
         Self {
             steps: vec![],
-            scaling: scaling as i32,
+            scale,
         }
     }
 
@@ -27,6 +25,39 @@ impl Transform {
         self
     }
 
+    /// Rotates about `pivot` instead of the origin. Pivots are in model units
+    /// and must sit on whole or half units for the result to stay on the grid.
+    pub fn rotate_about(self, rotation: Rotation, pivot: [f32; 3]) -> Self {
+        // Rotations turn each unit cube about its own centre, so the pivot is
+        // measured from there. Doubling keeps half units integral.
+        let [x, y, z] = pivot.map(|c| (2.0 * c - 1.0).round() as i32);
+        let (rx, ry, rz) = TransformStep::Rotate(rotation).apply(x, y, z, 1);
+        self.rotate(rotation)
+            .translate((x - rx) / 2, (y - ry) / 2, (z - rz) / 2)
+    }
+
+    /// Rotates by any angle about `pivot`. Quarter turns stay on the exact,
+    /// axis-aligned path; anything else has to be sampled when drawn.
+    pub fn rotate_by(self, axis: Axis, degrees: f32, pivot: [f32; 3]) -> Self {
+        if degrees % 90.0 != 0.0 {
+            return self.step(TransformStep::RotateBy {
+                axis,
+                degrees,
+                pivot,
+            });
+        }
+
+        let quarters = (degrees / 90.0).rem_euclid(4.0) as i32;
+        (0..quarters).fold(self, |t, _| t.rotate_about(axis.quarter(), pivot))
+    }
+
+    pub fn turn(self, turn: Turn, pivot: [f32; 3]) -> Self {
+        match turn {
+            Turn::Quarter(rotation) => self.rotate_about(rotation, pivot),
+            Turn::Degrees { axis, degrees } => self.rotate_by(axis, degrees, pivot),
+        }
+    }
+
     pub fn translate(mut self, dx: i32, dy: i32, dz: i32) -> Self {
         self.steps.push(TransformStep::Translate { dx, dy, dz });
         self
@@ -47,13 +78,17 @@ impl Transform {
         self
     }
 
+    /// Maps the unit cube at `(x, y, z)` to the first block it covers. Only
+    /// whole, uniform scales map cubes to whole blocks, so anything else has
+    /// to go through `apply_f64`.
     pub fn apply(&self, mut x: i32, mut y: i32, mut z: i32) -> (i32, i32, i32) {
-        x *= self.scaling;
-        y *= self.scaling;
-        z *= self.scaling;
+        let scaling = self.scale.whole().expect("scale isn't whole");
+        x *= scaling;
+        y *= scaling;
+        z *= scaling;
 
         for step in self.steps.iter() {
-            (x, y, z) = step.apply(x, y, z, self.scaling);
+            (x, y, z) = step.apply(x, y, z, scaling);
         }
 
         (x, y, z)
@@ -69,6 +104,57 @@ impl Transform {
         (x, y, z)
     }
 
+    /// Whether every step keeps the grid aligned, so faces can be drawn texel by
+    /// texel with `apply`.
+    pub fn is_axis_aligned(&self) -> bool {
+        !self
+            .steps
+            .iter()
+            .any(|s| matches!(s, TransformStep::RotateBy { .. }))
+    }
+
+    /// Maps a point in unscaled model units, where unit cubes are the cells
+    /// `apply` moves around.
+    pub fn apply_f64(&self, point: [f64; 3]) -> [f64; 3] {
+        self.steps.iter().fold(point, |p, step| step.apply_f64(p))
+    }
+
+    /// Undoes this transform's steps, last first.
+    pub fn inverse(&self) -> Self {
+        Self {
+            steps: self
+                .steps
+                .iter()
+                .rev()
+                .map(TransformStep::inverse)
+                .collect(),
+            scale: self.scale,
+        }
+    }
+
+    /// The inverse of `apply_f64`.
+    pub fn invert_f64(&self, point: [f64; 3]) -> [f64; 3] {
+        self.steps.iter().rev().fold(point, |p, step| step.invert_f64(p))
+    }
+
+    /// Like `rotate_only`, but including rotations by any angle.
+    pub fn rotate_only_f64(&self, mut vector: [f64; 3]) -> [f64; 3] {
+        for step in self.steps.iter() {
+            vector = match step {
+                TransformStep::Rotate(r) => {
+                    let (axis, degrees) = r.angle();
+                    axis.rotate(degrees, vector)
+                }
+                TransformStep::RotateBy { axis, degrees, .. } => {
+                    axis.rotate(*degrees as f64, vector)
+                }
+                _ => vector,
+            };
+        }
+
+        vector
+    }
+
     pub fn brush(&self, mut brush: Brush) -> Brush {
         for step in self.steps.iter() {
             brush = step.brush(brush);
@@ -78,6 +164,59 @@ impl Transform {
     }
 }
 
+/// How many blocks each model unit becomes along x, y and z.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Scale(pub [f64; 3]);
+
+impl Scale {
+    pub fn uniform(scale: f64) -> Self {
+        Self([scale; 3])
+    }
+
+    /// Parses one factor for every axis, like `2.5`, or one each, like `3x3x4`.
+    pub fn parse(text: &str) -> Option<Self> {
+        let factors = text
+            .split('x')
+            .map(|f| f.parse::<f64>().ok().filter(|f| f.is_finite() && *f > 0.0))
+            .collect::<Option<Vec<_>>>()?;
+        match factors[..] {
+            [s] => Some(Self::uniform(s)),
+            [x, y, z] => Some(Self([x, y, z])),
+            _ => None,
+        }
+    }
+
+    /// The number of blocks per unit, if it's whole and the same on every axis.
+    pub fn whole(&self) -> Option<i32> {
+        let [x, y, z] = self.0;
+        (x == y && y == z && x.fract() == 0.0).then_some(x as i32)
+    }
+
+    /// The centre of a block, in units.
+    pub fn centre(&self, block: [i32; 3]) -> [f64; 3] {
+        [0, 1, 2].map(|i| (block[i] as f64 + 0.5) / self.0[i])
+    }
+
+    pub fn min(&self) -> f64 {
+        self.0.into_iter().fold(f64::MAX, f64::min)
+    }
+
+    /// The blocks along `axis` with their centres from `low` up to `high`
+    /// units. Spans too thin to hold a centre get the block their middle is
+    /// in, so nothing disappears when scaled down.
+    pub fn blocks(&self, axis: usize, low: f64, high: f64) -> Range<i32> {
+        let scale = self.0[axis];
+        let first = (low * scale - 0.5).ceil() as i32;
+        let end = (high * scale - 0.5).ceil() as i32;
+        if first < end {
+            return first..end;
+        }
+
+        let middle = ((low + high) / 2.0 * scale).floor() as i32;
+        middle..middle + 1
+    }
+}
+
 #[derive(Debug, Copy, Clone, Deserialize)]
 pub enum TransformStep {
     #[serde(rename = "rotate")]
@@ -86,6 +225,12 @@ pub enum TransformStep {
     Translate { dx: i32, dy: i32, dz: i32 },
     #[serde(rename = "mirror")]
     Mirror(Plane),
+    #[serde(rename = "rotate-by")]
+    RotateBy {
+        axis: Axis,
+        degrees: f32,
+        pivot: [f32; 3],
+    },
 }
 
 impl TransformStep {
@@ -107,6 +252,68 @@ impl TransformStep {
                 Plane::XZ => (x, -y, z),
                 Plane::YZ => (-x, y, z),
             },
+            // Only an approximation, see `Transform::is_axis_aligned`.
+            TransformStep::RotateBy { .. } => {
+                let scaling = scaling as f64;
+                let centre = [x, y, z].map(|c| (c as f64 + 0.5) / scaling);
+                let [x, y, z] = self.apply_f64(centre).map(|c| (c * scaling).floor() as i32);
+                (x, y, z)
+            }
+        }
+    }
+
+    fn apply_f64(&self, [x, y, z]: [f64; 3]) -> [f64; 3] {
+        // Quarter turns and mirrors act on unit cubes, so they pivot about the
+        // centre of the cube at the origin.
+        match self {
+            TransformStep::Rotate(r) => {
+                let (axis, degrees) = r.angle();
+                let [x, y, z] = axis.rotate(degrees, [x - 0.5, y - 0.5, z - 0.5]);
+                [x + 0.5, y + 0.5, z + 0.5]
+            }
+            TransformStep::Translate { dx, dy, dz } => {
+                [x + *dx as f64, y + *dy as f64, z + *dz as f64]
+            }
+            TransformStep::Mirror(p) => match p {
+                Plane::XY => [x, y, 1.0 - z],
+                Plane::XZ => [x, 1.0 - y, z],
+                Plane::YZ => [1.0 - x, y, z],
+            },
+            TransformStep::RotateBy {
+                axis,
+                degrees,
+                pivot,
+            } => {
+                let [px, py, pz] = pivot.map(|c| c as f64);
+                let [x, y, z] = axis.rotate(*degrees as f64, [x - px, y - py, z - pz]);
+                [x + px, y + py, z + pz]
+            }
+        }
+    }
+
+    fn invert_f64(&self, point: [f64; 3]) -> [f64; 3] {
+        self.inverse().apply_f64(point)
+    }
+
+    /// The step that undoes this one.
+    fn inverse(&self) -> TransformStep {
+        match *self {
+            TransformStep::Rotate(r) => TransformStep::Rotate(r.opposite()),
+            TransformStep::Translate { dx, dy, dz } => TransformStep::Translate {
+                dx: -dx,
+                dy: -dy,
+                dz: -dz,
+            },
+            TransformStep::Mirror(p) => TransformStep::Mirror(p),
+            TransformStep::RotateBy {
+                axis,
+                degrees,
+                pivot,
+            } => TransformStep::RotateBy {
+                axis,
+                degrees: -degrees,
+                pivot,
+            },
         }
     }
 
@@ -156,6 +363,7 @@ impl TransformStep {
                 (Plane::YZ, Brush::XNeg) => Brush::XPos,
                 (_, b) => b,
             },
+            TransformStep::RotateBy { .. } => brush,
         }
     }
 }
@@ -177,6 +385,68 @@ pub enum Rotation {
     ZNeg,
 }
 
+impl Rotation {
+    fn opposite(&self) -> Rotation {
+        match self {
+            Rotation::XPos => Rotation::XNeg,
+            Rotation::XNeg => Rotation::XPos,
+            Rotation::YPos => Rotation::YNeg,
+            Rotation::YNeg => Rotation::YPos,
+            Rotation::ZPos => Rotation::ZNeg,
+            Rotation::ZNeg => Rotation::ZPos,
+        }
+    }
+
+    fn angle(&self) -> (Axis, f64) {
+        match self {
+            Rotation::XPos => (Axis::X, 90.0),
+            Rotation::XNeg => (Axis::X, -90.0),
+            Rotation::YPos => (Axis::Y, 90.0),
+            Rotation::YNeg => (Axis::Y, -90.0),
+            Rotation::ZPos => (Axis::Z, 90.0),
+            Rotation::ZNeg => (Axis::Z, -90.0),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, Deserialize)]
+pub enum Axis {
+    #[serde(rename = "x")]
+    X,
+    #[serde(rename = "y")]
+    Y,
+    #[serde(rename = "z")]
+    Z,
+}
+
+impl Axis {
+    fn quarter(&self) -> Rotation {
+        match self {
+            Axis::X => Rotation::XPos,
+            Axis::Y => Rotation::YPos,
+            Axis::Z => Rotation::ZPos,
+        }
+    }
+
+    /// Rotates counterclockwise when looking down the axis towards the origin.
+    fn rotate(&self, degrees: f64, [x, y, z]: [f64; 3]) -> [f64; 3] {
+        let (sin, cos) = degrees.to_radians().sin_cos();
+        match self {
+            Axis::X => [x, y * cos - z * sin, y * sin + z * cos],
+            Axis::Y => [x * cos + z * sin, y, z * cos - x * sin],
+            Axis::Z => [x * cos - y * sin, x * sin + y * cos, z],
+        }
+    }
+}
+
+/// Either a quarter turn like `"+x"` or `{"axis": "x", "degrees": 30}`.
+#[derive(Debug, Copy, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum Turn {
+    Quarter(Rotation),
+    Degrees { axis: Axis, degrees: f32 },
+}
+
 #[allow(unused)]
 #[derive(Debug, Copy, Clone, Deserialize)]
 pub enum Plane {
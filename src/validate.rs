@@ -0,0 +1,141 @@
+use crate::model::JsonModel;
+use crate::TEXTURE_DIR;
+use std::collections::HashMap;
+use std::fmt;
+
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub enum Severity {
+    /// The model can't be drawn as intended.
+    Error,
+    /// The model can be drawn, but probably isn't what was meant.
+    Warning,
+}
+
+/// Something wrong with a model, found by `JsonModel::validate`.
+pub struct Problem {
+    pub severity: Severity,
+    pub model: String,
+    pub part: Option<String>,
+    pub message: String,
+}
+
+impl Problem {
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let severity = match self.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+        write!(f, "{}: {}", severity, self.model)?;
+        if let Some(part) = &self.part {
+            write!(f, ", part \"{}\"", part)?;
+        }
+        write!(f, ": {}", self.message)
+    }
+}
+
+/// Collects the problems found in one model.
+pub struct Report {
+    model: String,
+    pub problems: Vec<Problem>,
+}
+
+impl Report {
+    pub fn new(model: &str) -> Self {
+        Self {
+            model: model.to_string(),
+            problems: vec![],
+        }
+    }
+
+    pub fn error(&mut self, part: Option<&str>, message: impl ToString) {
+        self.add(Severity::Error, part, message);
+    }
+
+    pub fn warning(&mut self, part: Option<&str>, message: impl ToString) {
+        self.add(Severity::Warning, part, message);
+    }
+
+    fn add(&mut self, severity: Severity, part: Option<&str>, message: impl ToString) {
+        self.problems.push(Problem {
+            severity,
+            model: self.model.clone(),
+            part: part.map(str::to_string),
+            message: message.to_string(),
+        });
+    }
+}
+
+/// Checks every model in `models.json`, or only the one called `name`, against
+/// the textures they name, and prints what's wrong with them.
+pub fn validate_models(models: &[JsonModel], name: Option<&str>) {
+    let mut checked = 0;
+    let (mut errors, mut warnings) = (0, 0);
+    for model in models.iter().filter(|m| name.is_none_or(|n| m.name == n)) {
+        checked += 1;
+
+        let mut report = Report::new(&model.name);
+        let texture = texture_size(&model.texture, model.texture_size, &mut report);
+        let textures = model
+            .textures
+            .iter()
+            .map(|(name, path)| {
+                let declared = model.texture_sizes.get(name).copied();
+                (name.clone(), texture_size(path, declared, &mut report))
+            })
+            .collect::<Vec<_>>();
+        let mut textures = textures
+            .into_iter()
+            .filter_map(|(name, size)| Some((name, size?)))
+            .collect::<HashMap<_, _>>();
+        for (name, size) in model.texture_sizes.iter() {
+            textures.entry(name.clone()).or_insert(*size);
+        }
+
+        report.problems.extend(model.validate(texture, &textures));
+        for problem in report.problems.iter() {
+            println!("{problem}");
+            match problem.severity {
+                Severity::Error => errors += 1,
+                Severity::Warning => warnings += 1,
+            }
+        }
+    }
+
+    match name {
+        Some(name) if checked == 0 => println!("Unknown model \"{name}\""),
+        _ => println!("Checked {checked} models: {errors} errors, {warnings} warnings"),
+    }
+}
+
+/// The size of a texture, relative to the texture directory, or the size the
+/// model declares for it if it can't be read.
+fn texture_size(
+    path: &str,
+    declared: Option<(u32, u32)>,
+    report: &mut Report,
+) -> Option<(u32, u32)> {
+    let size = image::image_dimensions(format!("{}/{}", TEXTURE_DIR, path));
+    match (size, declared) {
+        (Ok((width, height)), Some(size)) if size != (width, height) => {
+            let message = format!(
+                "texture \"{path}\" is {width}x{height}, not the declared {}x{}",
+                size.0, size.1
+            );
+            report.warning(None, message);
+            Some((width, height))
+        }
+        (Ok(size), _) => Some(size),
+        (Err(_), Some(size)) => Some(size),
+        (Err(_), None) => {
+            let message = format!("can't read texture \"{path}\", so UVs on it weren't checked");
+            report.warning(None, message);
+            None
+        }
+    }
+}